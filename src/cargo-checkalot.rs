@@ -1,6 +1,6 @@
 use clap::{Parser, Subcommand};
 
-use checkalot::mains::{main as main_impl, Flags};
+use checkalot::mains::{cargo_main, Flags};
 
 #[derive(Parser)]
 #[clap(verbatim_doc_comment)]
@@ -16,5 +16,5 @@ enum Command {
 
 fn main() -> anyhow::Result<()> {
     let Command::Checkalot(args) = MainFlags::parse().command;
-    main_impl(args)
+    cargo_main(args)
 }
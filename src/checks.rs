@@ -1,12 +1,32 @@
+use std::collections::BTreeMap;
 use std::path::Path;
+use std::time::{Duration, Instant};
 
 use crate::errors::{CheckError, RunCommandError};
-use crate::{toolchain, Check, CommandSpec};
+use crate::variables::Variables;
+use crate::{toolchain, Check, CheckEntry, CommandSpec};
 
-fn run_command(command_spec: &CommandSpec, dir: &Path) -> Result<String, RunCommandError> {
-    let command = shell_words::split(command_spec.command())?;
+/// Run a command in `dir`, with additional environment variables, killing it after `timeout`.
+/// If `variables` are provided, the placeholders of the command are replaced.
+pub(crate) fn run_command(
+    command_spec: &CommandSpec,
+    dir: &Path,
+    env: &BTreeMap<String, String>,
+    timeout: Option<Duration>,
+    variables: Option<&Variables>,
+) -> Result<String, RunCommandError> {
+    let command = match variables {
+        Some(variables) => variables
+            .command(command_spec.command(), dir)
+            .map_err(RunCommandError::Variable)?,
+        None => command_spec.command().clone(),
+    };
+    let command = shell_words::split(&command)?;
     let command_name = command[0].clone();
     let mut cmd = duct::cmd(&command_name, command.into_iter().skip(1)).dir(dir);
+    for (key, value) in env {
+        cmd = cmd.env(key, value);
+    }
 
     // If a rust-toolchain.toml is present in the execution folder, we override RUSTC_TOOLCHAIN.
     // This avoids the bug described in https://github.com/cpg314/checkalot/issues/2, when
@@ -18,27 +38,68 @@ fn run_command(command_spec: &CommandSpec, dir: &Path) -> Result<String, RunComm
             cmd = cmd.env(toolchain::ENVVAR, toolchain_toml.0);
         }
     }
-    run_expr(&command_name, cmd, command_spec.success_statuses())
+    run_expr_timeout(&command_name, cmd, command_spec.success_statuses(), timeout)
+}
+
+/// Find a version number in the output of a `version_command`.
+pub(crate) fn parse_version(output: &str) -> Option<semver::Version> {
+    output
+        .trim()
+        .split(' ')
+        .find_map(|s| semver::Version::parse(s).ok())
+}
+
+pub(crate) fn run_expr(
+    command_name: &str,
+    expr: duct::Expression,
+    success_statuses: &[i32],
+) -> Result<String, RunCommandError> {
+    run_expr_timeout(command_name, expr, success_statuses, None)
+}
+
+/// Wait for the expression to finish, returning `None` if it was killed after `timeout`.
+fn run_with_timeout(
+    expr: duct::Expression,
+    timeout: Duration,
+) -> std::io::Result<Option<std::process::Output>> {
+    let handle = expr.start()?;
+    let start = Instant::now();
+    loop {
+        if let Some(output) = handle.try_wait()? {
+            return Ok(Some(output.clone()));
+        }
+        if start.elapsed() > timeout {
+            handle.kill()?;
+            return Ok(None);
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    }
 }
 
-fn run_expr(
+fn run_expr_timeout(
     command_name: &str,
     expr: duct::Expression,
     success_statuses: &[i32],
+    timeout: Option<Duration>,
 ) -> Result<String, RunCommandError> {
-    let out = expr
+    let expr = expr
         .stderr_to_stdout()
         .stdout_capture()
         .stderr_capture()
-        .unchecked()
-        .run();
+        .unchecked();
+    let out = match timeout {
+        Some(timeout) => run_with_timeout(expr, timeout),
+        None => expr.run().map(Some),
+    };
     match &out {
         Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
             return Err(RunCommandError::NotFound(command_name.into()));
         }
         _ => {}
     }
-    let out = out.map_err(RunCommandError::Other)?;
+    let out = out
+        .map_err(RunCommandError::Other)?
+        .ok_or_else(|| RunCommandError::Timeout(timeout.unwrap_or_default()))?;
     let stdout = String::from_utf8(out.stdout).map_err(|_| RunCommandError::Utf8)?;
     match out.status.code() {
         Some(code) if !success_statuses.contains(&code) => Err(RunCommandError::StatusCode {
@@ -49,16 +110,51 @@ fn run_expr(
         _ => Ok(stdout),
     }
 }
+impl CheckEntry {
+    pub fn name(&self) -> &str {
+        self.qualified_name
+            .as_deref()
+            .unwrap_or_else(|| self.check.name())
+    }
+    pub fn paths(&self) -> &[String] {
+        self.check.paths()
+    }
+}
+
 impl Check {
     pub fn name(&self) -> &str {
         match self {
             Check::Version { .. } => "version",
-            Check::GitClean => "git-is-clean",
-            Check::GitRebased => "git-is-rebased",
+            Check::GitClean {} => "git-is-clean",
+            Check::GitRebased { .. } => "git-is-rebased",
+            Check::CommitMessages(_) => "commit-messages",
+            Check::Files(_) => "files",
+            Check::Whitespace(_) => "whitespace",
+            Check::LicenseHeader(_) => "license-header",
+            Check::Pattern(_) => "pattern",
+            Check::Secrets(_) => "secrets",
+            Check::Todos(_) => "todos",
+            Check::Syntax(_) => "syntax",
+            Check::Preset(preset) => preset.name(),
             Check::Command { name, .. } => name,
         }
     }
-    pub fn execute(&self, repository: &Path, fix: bool) -> Result<(), CheckError> {
+    /// Glob patterns (where `*` also matches `/`) of the files concerned by the check. Empty if the check concerns the whole
+    /// repository.
+    pub fn paths(&self) -> &[String] {
+        match self {
+            Check::Command { paths, .. } => paths,
+            _ => &[],
+        }
+    }
+    /// Execute the check in `repository`, or in the folder of its configuration for nested
+    /// configurations.
+    pub fn execute(
+        &self,
+        repository: &Path,
+        fix: bool,
+        variables: &Variables,
+    ) -> Result<(), CheckError> {
         match self {
             Check::Version {
                 version: version_req,
@@ -73,51 +169,67 @@ impl Check {
                 }
                 Ok(())
             }
-            Check::GitClean => {
+            Check::GitClean {} => {
                 if fix {
                     return Err(CheckError::NoFix);
                 }
-                let stdout = run_expr(
-                    "git",
-                    duct::cmd!("git", "status", "--porcelain", "-uno").dir(repository),
-                    &[0],
-                )?;
+                let stdout = crate::git::git(repository, &["status", "--porcelain", "-uno"])?;
                 if !stdout.is_empty() {
                     return Err(CheckError::DirtyRepository);
                 }
                 Ok(())
             }
-            Check::GitRebased => {
-                if fix {
-                    return Err(CheckError::NoFix);
+            Check::GitRebased {
+                base,
+                remote,
+                fetch,
+            } => {
+                let git = |args: &[&str]| crate::git::git(repository, args);
+                let remote = remote.as_deref().unwrap_or("origin");
+                if *fetch {
+                    git(&["fetch", remote])?;
                 }
-                run_expr("git", duct::cmd!("git", "fetch").dir(repository), &[0])?;
-                let rev_parse = |rev: &str| -> Result<String, RunCommandError> {
-                    Ok(run_expr(
-                        "git",
-                        duct::cmd!("git", "rev-parse", rev).dir(repository),
-                        &[0],
-                    )?
-                    .trim()
-                    .to_owned())
+                let base = match base {
+                    Some(base) => base.clone(),
+                    None => crate::git::default_base(repository, remote)
+                        .ok_or_else(|| CheckError::NoBase(remote.into()))?,
                 };
-                let origin = rev_parse("origin/master")?;
-                let head = rev_parse("HEAD")?;
-                let common_ancestor = run_expr(
-                    "git",
-                    duct::cmd!("git", "merge-base", &origin, &head).dir(repository),
-                    &[0],
-                )?;
-
-                if common_ancestor.trim() != origin {
-                    return Err(CheckError::NotRebased {
-                        local: head,
-                        origin,
-                    });
+                if fix {
+                    if let Err(e) = git(&["rebase", &base]) {
+                        // Leave the repository as it was.
+                        let _ = git(&["rebase", "--abort"]);
+                        let output = match e {
+                            RunCommandError::ToolFailed { output, .. } => output,
+                            e => e.to_string(),
+                        };
+                        return Err(CheckError::RebaseFailed { base, output });
+                    }
+                    return Ok(());
+                }
+                let range = format!("HEAD..{}", base);
+                let behind = git(&["rev-list", "--count", &range])?;
+                let behind: usize =
+                    behind
+                        .trim()
+                        .parse()
+                        .map_err(|_| RunCommandError::UnexpectedOutput {
+                            command: format!("git rev-list --count {}", range),
+                            output: behind.trim().into(),
+                        })?;
+                if behind > 0 {
+                    return Err(CheckError::NotRebased { base, behind });
                 }
-
                 Ok(())
             }
+            Check::CommitMessages(check) => check.execute(repository, fix),
+            Check::Files(check) => check.execute(repository, fix),
+            Check::Whitespace(check) => check.execute(repository, fix),
+            Check::LicenseHeader(check) => check.execute(repository, fix),
+            Check::Pattern(check) => check.execute(repository, fix),
+            Check::Secrets(check) => check.execute(repository, fix),
+            Check::Todos(check) => check.execute(repository, fix),
+            Check::Syntax(check) => check.execute(repository, fix),
+            Check::Preset(preset) => Err(CheckError::UnexpandedPreset(preset.name().into())),
             Check::Command {
                 command,
                 folder,
@@ -125,11 +237,19 @@ impl Check {
                 version,
                 version_command,
                 output,
+                env,
+                timeout,
                 ..
             } => {
+                let timeout = timeout.map(Duration::from_secs);
+                let interpolate = |path: &Path, folder: Option<&Path>| {
+                    variables
+                        .path(path, folder)
+                        .map_err(RunCommandError::Variable)
+                };
                 let mut dir = repository.to_owned();
                 if let Some(folder) = folder {
-                    dir = dir.join(folder);
+                    dir = dir.join(interpolate(folder, None)?);
                 }
                 if !dir.exists() {
                     return Err(CheckError::ExecutionFolder(dir));
@@ -140,12 +260,10 @@ impl Check {
                     }
                     (Some(version_req), Some(version_command)) => {
                         // Check version
-                        let out = run_command(version_command, &dir)?;
-                        let version = out
-                            .trim()
-                            .split(' ')
-                            .find_map(|s| semver::Version::parse(s).ok())
-                            .ok_or_else(|| CheckError::VersionFind(out))?;
+                        let out = run_command(version_command, &dir, env, timeout, Some(variables))
+                            .map_err(|e| e.tool(version_command.command()))?;
+                        let version =
+                            parse_version(&out).ok_or_else(|| CheckError::VersionFind(out))?;
 
                         if !version_req.matches(&version) {
                             return Err(CheckError::VersionReq {
@@ -159,18 +277,23 @@ impl Check {
 
                 if fix {
                     let command = fix_command.as_ref().ok_or(CheckError::NoFix)?;
-                    run_command(command, &dir)?;
+                    run_command(command, &dir, env, timeout, Some(variables))?;
                 } else {
-                    let out = run_command(command, &dir);
+                    let command = command.as_ref().ok_or(CheckError::MissingCommand)?;
+                    let out = run_command(command, &dir, env, timeout, Some(variables));
                     if let Some(output_path) = output {
+                        let output_path = interpolate(output_path, Some(&dir))?;
+                        if let Some(parent) = output_path.parent() {
+                            std::fs::create_dir_all(parent).map_err(CheckError::WriteOutput)?;
+                        }
                         // Write to output file
                         match &out {
                             Ok(stdout) => {
-                                std::fs::write(output_path, stdout)
+                                std::fs::write(&output_path, stdout)
                                     .map_err(CheckError::WriteOutput)?;
                             }
                             Err(RunCommandError::StatusCode { output, .. }) => {
-                                std::fs::write(output_path, output)
+                                std::fs::write(&output_path, output)
                                     .map_err(CheckError::WriteOutput)?;
                             }
                             _ => {}
@@ -0,0 +1,203 @@
+//! Built-in `commit-messages` check, linting the messages of the commits of the branch.
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::errors::{CheckError, Finding};
+
+/// Types of the Conventional Commits specification and its usual extensions.
+const DEFAULT_TYPES: [&str; 11] = [
+    "build", "chore", "ci", "docs", "feat", "fix", "perf", "refactor", "revert", "style", "test",
+];
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct CommitMessages {
+    /// Base revision, e.g. `origin/main`. The commits since the merge base with HEAD are checked.
+    /// Defaults to the default branch of `remote`, so that the pushed commits of a feature branch
+    /// are checked as well.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    base: Option<String>,
+    /// Remote used to find the default base. Defaults to `origin`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    remote: Option<String>,
+    /// Require subjects following Conventional Commits, e.g. `feat(ui): add colors`.
+    #[serde(
+        default = "crate::config::default_true",
+        skip_serializing_if = "crate::config::is_true"
+    )]
+    conventional: bool,
+    /// Allowed Conventional Commits types. Defaults to `build`, `chore`, `ci`, `docs`, `feat`,
+    /// `fix`, `perf`, `refactor`, `revert`, `style` and `test`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    types: Option<Vec<String>>,
+    /// Maximal number of characters of the subject.
+    #[serde(default = "default_max_subject_length")]
+    max_subject_length: usize,
+    /// Required trailers, with a regular expression their value must match, e.g.
+    /// `Refs: '^#\d+$'`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    trailers: BTreeMap<String, String>,
+    /// Allow `fixup!`, `squash!` and `amend!` commits, which are meant to be squashed before
+    /// merging.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    allow_fixups: bool,
+}
+
+fn default_max_subject_length() -> usize {
+    72
+}
+
+struct Rules<'a> {
+    config: &'a CommitMessages,
+    conventional: Regex,
+    trailers: Vec<(&'a str, Regex)>,
+}
+
+impl<'a> Rules<'a> {
+    fn new(config: &'a CommitMessages) -> Result<Self, regex::Error> {
+        Ok(Self {
+            config,
+            conventional: Regex::new(r"^(?<type>\w+)(\([^()]+\))?!?: \S")?,
+            trailers: config
+                .trailers
+                .iter()
+                .map(|(key, pattern)| Ok((key.as_str(), Regex::new(pattern)?)))
+                .collect::<Result<_, regex::Error>>()?,
+        })
+    }
+    /// Problems of a commit, given its message and its trailers (one `Key: value` per line).
+    fn lint(&self, message: &str, trailers: &str) -> Vec<String> {
+        let mut problems = vec![];
+        let subject = message.lines().next().unwrap_or_default();
+        if ["fixup!", "squash!", "amend!"]
+            .iter()
+            .any(|p| subject.starts_with(p))
+        {
+            if !self.config.allow_fixups {
+                problems.push("fixup commit, to be squashed".into());
+            }
+            // The other rules apply to the commit it will be squashed into.
+            return problems;
+        }
+        if self.config.conventional {
+            match self.conventional.captures(subject) {
+                None => problems
+                    .push("not a Conventional Commit, expected `type(scope): description`".into()),
+                Some(captures) => {
+                    let kind = &captures["type"];
+                    let allowed = match &self.config.types {
+                        Some(types) => types.iter().any(|t| t == kind),
+                        None => DEFAULT_TYPES.contains(&kind),
+                    };
+                    if !allowed {
+                        problems.push(format!("unknown type `{}`", kind));
+                    }
+                }
+            }
+        }
+        let length = subject.chars().count();
+        if length > self.config.max_subject_length {
+            problems.push(format!(
+                "subject has {} characters, more than {}",
+                length, self.config.max_subject_length
+            ));
+        }
+        for (key, pattern) in &self.trailers {
+            let found = trailers.lines().any(|line| {
+                line.split_once(':').is_some_and(|(k, v)| {
+                    k.trim().eq_ignore_ascii_case(key) && pattern.is_match(v.trim())
+                })
+            });
+            if !found {
+                problems.push(format!(
+                    "missing trailer `{}` matching `{}`",
+                    key,
+                    pattern.as_str()
+                ));
+            }
+        }
+        problems
+    }
+}
+
+impl CommitMessages {
+    pub(crate) fn execute(&self, repository: &Path, fix: bool) -> Result<(), CheckError> {
+        if fix {
+            return Err(CheckError::NoFix);
+        }
+        let rules = Rules::new(self)?;
+        let remote = self.remote.as_deref().unwrap_or("origin");
+        let base = match &self.base {
+            Some(base) => base.clone(),
+            None => crate::git::remote_head(repository, remote)
+                .ok_or_else(|| CheckError::NoRemoteHead(remote.into()))?,
+        };
+        let merge_base = crate::git::git(repository, &["merge-base", &base, "HEAD"])?;
+        // Short sha, message and trailers of each commit, separated by NUL and RS characters.
+        let log = crate::git::git(
+            repository,
+            &[
+                "log",
+                "--no-merges",
+                "--format=%h%x00%B%x00%(trailers:only,unfold)%x1e",
+                &format!("{}..HEAD", merge_base.trim()),
+            ],
+        )?;
+        let findings: Vec<_> = log
+            .split('\x1e')
+            .filter_map(|commit| {
+                let mut fields = commit.trim_start().splitn(3, '\0');
+                Some((fields.next()?, fields.next()?, fields.next()?))
+            })
+            .flat_map(|(sha, message, trailers)| {
+                let subject = message.lines().next().unwrap_or_default();
+                rules
+                    .lint(message, trailers)
+                    .into_iter()
+                    .map(move |problem| Finding {
+                        location: sha.into(),
+                        line: None,
+                        column: None,
+                        message: format!("{} ({})", problem, subject),
+                    })
+            })
+            .collect();
+        if !findings.is_empty() {
+            return Err(CheckError::Findings(findings));
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn lint_messages() {
+    let config: CommitMessages = serde_yaml::from_str(
+        "max_subject_length: 30
+trailers: {Refs: '^#\\d+$'}",
+    )
+    .unwrap();
+    let rules = Rules::new(&config).unwrap();
+    assert!(rules
+        .lint("feat(ui)!: add colors\n\nRefs: #12\n", "Refs: #12\n")
+        .is_empty());
+    assert_eq!(
+        rules.lint("Add colors to the user interface", "Refs: JIRA-1\n"),
+        [
+            "not a Conventional Commit, expected `type(scope): description`",
+            "subject has 32 characters, more than 30",
+            "missing trailer `Refs` matching `^#\\d+$`"
+        ]
+    );
+    assert_eq!(
+        rules.lint("feature: colors", "refs: #1"),
+        ["unknown type `feature`"]
+    );
+    assert_eq!(
+        rules.lint("fixup! feat: add colors", ""),
+        ["fixup commit, to be squashed"]
+    );
+}
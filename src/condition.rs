@@ -0,0 +1,132 @@
+//! Predicates deciding whether a check runs, with `when`.
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum Condition {
+    /// A file or folder exists, relative to the folder of the configuration.
+    FileExists(PathBuf),
+    /// An environment variable is set, or has the given values.
+    Env(EnvCondition),
+    /// The operating system, e.g. `linux`, `macos` or `windows`.
+    Os(String),
+    /// Running in CI, as denoted by the `CI` environment variable.
+    Ci(bool),
+    /// An executable is found in the `PATH`.
+    CommandExists(String),
+    All(Vec<Condition>),
+    Any(Vec<Condition>),
+    Not(Box<Condition>),
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+#[serde(untagged)]
+pub enum EnvCondition {
+    /// Name of a variable that must be set.
+    Set(String),
+    /// Variables with their required values.
+    Equals(BTreeMap<String, String>),
+}
+
+impl std::fmt::Display for Condition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let list = |conditions: &[Condition]| {
+            conditions
+                .iter()
+                .map(|c| c.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        match self {
+            Condition::FileExists(path) => write!(f, "file_exists({})", path.display()),
+            Condition::Env(EnvCondition::Set(name)) => write!(f, "env({})", name),
+            Condition::Env(EnvCondition::Equals(values)) => {
+                let values: Vec<_> = values.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+                write!(f, "env({})", values.join(", "))
+            }
+            Condition::Os(os) => write!(f, "os({})", os),
+            Condition::Ci(ci) => write!(f, "ci({})", ci),
+            Condition::CommandExists(command) => write!(f, "command_exists({})", command),
+            Condition::All(conditions) => write!(f, "all({})", list(conditions)),
+            Condition::Any(conditions) => write!(f, "any({})", list(conditions)),
+            Condition::Not(condition) => write!(f, "not({})", condition),
+        }
+    }
+}
+
+fn is_ci() -> bool {
+    std::env::var("CI").is_ok_and(|v| !v.is_empty() && v != "false" && v != "0")
+}
+
+fn command_exists(command: &str) -> bool {
+    let Some(path) = std::env::var_os("PATH") else {
+        return false;
+    };
+    std::env::split_paths(&path).any(|dir| {
+        let path = dir.join(command);
+        path.is_file() && is_executable(&path)
+    })
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata()
+        .is_ok_and(|m| m.permissions().mode() & 0o111 != 0)
+}
+#[cfg(not(unix))]
+fn is_executable(_path: &Path) -> bool {
+    true
+}
+
+impl Condition {
+    /// Evaluate the condition, with relative paths resolved from `folder`.
+    pub fn evaluate(&self, folder: &Path) -> bool {
+        match self {
+            Condition::FileExists(path) => folder.join(path).exists(),
+            Condition::Env(EnvCondition::Set(name)) => std::env::var_os(name).is_some(),
+            Condition::Env(EnvCondition::Equals(values)) => values
+                .iter()
+                .all(|(k, v)| std::env::var(k).is_ok_and(|value| &value == v)),
+            Condition::Os(os) => std::env::consts::OS == os,
+            Condition::Ci(ci) => is_ci() == *ci,
+            Condition::CommandExists(command) => command_exists(command),
+            Condition::All(conditions) => conditions.iter().all(|c| c.evaluate(folder)),
+            Condition::Any(conditions) => conditions.iter().any(|c| c.evaluate(folder)),
+            Condition::Not(condition) => !condition.evaluate(folder),
+        }
+    }
+}
+
+#[test]
+fn evaluate_conditions() {
+    let checks: Vec<crate::CheckEntry> = serde_yaml::from_str(
+        "
+- type: git-is-clean
+  when:
+    all:
+      - file_exists: Cargo.toml
+      - not:
+          env: CHECKALOT_UNSET_VARIABLE
+      - any:
+          - command_exists: cargo
+          - os: plan9
+- type: git-is-rebased
+  when:
+    env: {CHECKALOT_UNSET_VARIABLE: '1'}
+",
+    )
+    .unwrap();
+    let condition = checks[0].when.as_ref().unwrap();
+    let folder = Path::new(env!("CARGO_MANIFEST_DIR"));
+    assert!(condition.evaluate(folder));
+    assert_eq!(
+        condition.to_string(),
+        "all(file_exists(Cargo.toml), not(env(CHECKALOT_UNSET_VARIABLE)), any(command_exists(cargo), os(plan9)))"
+    );
+    assert!(!checks[1].when.as_ref().unwrap().evaluate(folder));
+}
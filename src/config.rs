@@ -1,56 +1,122 @@
-use std::collections::HashSet;
-use std::io::Seek;
+use std::collections::{BTreeMap, HashSet};
 use std::path::{Path, PathBuf};
 
 use anyhow::Context;
 use colored::Colorize;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-#[derive(Deserialize, Serialize, Debug)]
+use crate::errors::{ExitCode, WithExitCode};
+use crate::select::Selector;
+use crate::ui::Reporter;
+use crate::validate::{Diagnostics, Source};
+
+/// Configuration of checkalot, usually stored in `checkalot.yaml`.
+#[derive(Deserialize, Serialize, Debug, JsonSchema, Default)]
+#[serde(deny_unknown_fields)]
 pub struct Config {
+    /// Checks, executed in order.
     #[serde(default)]
-    pub checks: Vec<Check>,
-    bundle: Option<BundleConfig>,
+    pub checks: Vec<CheckEntry>,
+    /// Bundle of tools, created with checkalot-bundle.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) bundle: Option<BundleConfig>,
+    /// Configurations whose checks are included before the ones of this file.
+    /// Checks of this file with the same name replace the included ones.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) extends: Vec<Extends>,
+    /// Names of included checks, or of checks from presets, to remove.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) remove: Vec<String>,
+    /// Parameterised check bodies, used by command checks with `uses`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub(crate) templates: BTreeMap<String, crate::templates::Template>,
+    /// Named selections of checks and options, chosen with `--profile`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, Profile>,
+    /// Checks disabled by local configurations, with their file.
+    #[serde(skip)]
+    pub(crate) disabled: Vec<(String, String)>,
+}
+
+/// Selection of checks and options, e.g. for a pre-commit hook or for CI.
+/// These are combined with the command line arguments.
+#[derive(Deserialize, Serialize, Debug, JsonSchema, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    /// Only perform these checks.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub only: Vec<String>,
+    /// Skip these checks.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skip: Vec<String>,
+    /// Only perform the checks matching this expression, e.g. `tag:rust and not name:clippy*`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub select: Option<String>,
+    /// Additional checks, replacing the ones with the same name.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub checks: Vec<CheckEntry>,
+    /// Skip checks whose `paths` do not match files changed since this git revision.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub changed_since: Option<String>,
+    /// Run all checks even if some fail.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub keep_going: bool,
+    /// Write a JSON report of the results at this location.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub report: Option<PathBuf>,
 }
 
-#[derive(Deserialize, Serialize, Debug)]
+/// Configuration to include.
+#[derive(Deserialize, Serialize, Debug, JsonSchema)]
+#[serde(untagged, deny_unknown_fields)]
+pub enum Extends {
+    /// Path, relative to the including configuration.
+    Path(PathBuf),
+    /// Remote configuration, pinned by its checksum.
+    Url {
+        url: String,
+        /// SHA-256 checksum of the configuration file.
+        sha256: String,
+    },
+}
+
+#[derive(Deserialize, Serialize, Debug, JsonSchema)]
+#[serde(deny_unknown_fields)]
 pub struct BundleConfig {
+    /// URL of the .tar.gz bundle.
     url: String,
+    /// SHA-256 checksum of the bundle.
     sha256: String,
     #[serde(skip)]
     path: PathBuf,
 }
 
+impl std::fmt::Display for Extends {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Extends::Path(path) => write!(f, "{:?}", path),
+            Extends::Url { url, .. } => write!(f, "{}", url),
+        }
+    }
+}
+
 impl Config {
-    pub fn download_bundle(&mut self) -> anyhow::Result<()> {
+    pub fn download_bundle(&mut self, reporter: &mut dyn Reporter) -> anyhow::Result<()> {
         if let Some(config) = &mut self.bundle {
-            config.path = dirs::cache_dir()
-                .context("Failed to find cache dir")?
-                .join("checkalot")
-                .join(&config.sha256);
+            config.path = crate::cache_dir()?.join(&config.sha256);
             std::fs::create_dir_all(&config.path)?;
             let cache_done = config.path.join("done");
             if !cache_done.exists() {
-                println!("Downloading bundle from {}...", config.url);
-
-                let mut reader = ureq::get(&config.url).call()?.into_reader();
-                let mut tempfile = tempfile::tempfile()?;
-                std::io::copy(&mut reader, &mut tempfile)?;
-                tempfile.seek(std::io::SeekFrom::Start(0))?;
-                let sha256 = crate::sha256(&mut tempfile)?;
-                anyhow::ensure!(
-                    config.sha256 == sha256,
-                    "Mismatching checksum: got {}, wanted {}",
-                    sha256,
-                    config.sha256
-                );
-                tempfile.seek(std::io::SeekFrom::Start(0))?;
+                reporter.info(&format!("Downloading bundle from {}...", config.url));
+
+                let tempfile = crate::download(&config.url, &config.sha256)?;
                 let reader = flate2::read::GzDecoder::new(tempfile);
                 let mut tar = tar::Archive::new(reader);
                 tar.unpack(&config.path)?;
                 std::fs::write(cache_done, "")?;
             } else {
-                println!("Using bundle from {:?}", config.path);
+                reporter.info(&format!("Using bundle from {:?}", config.path));
             }
             let path = std::env::var("PATH").unwrap_or_default();
             std::env::set_var(
@@ -60,48 +126,231 @@ impl Config {
         }
         Ok(())
     }
-    pub fn load(path: &Path) -> anyhow::Result<Self> {
-        let config = std::fs::read_to_string(path)
-            .with_context(|| format!("Failed to open configuration at {:?}", path))?;
-        let mut config: Config =
-            serde_yaml::from_str(&config).context("Failed to deserialize configuration")?;
+    /// Folder of the downloaded bundle, if any.
+    pub fn bundle_dir(&self) -> Option<&Path> {
+        self.bundle.as_ref().map(|b| b.path.as_path())
+    }
+    /// JSON schema of the configuration, for validation and autocompletion in editors.
+    pub fn schema() -> String {
+        let schema = schemars::schema_for!(Config);
+        serde_json::to_string_pretty(&schema).unwrap() + "\n"
+    }
+    /// Read the configuration and, if `repository` is set, the configurations nested in it.
+    /// Then apply the personal overrides, and download the bundle. Downloads are reported to
+    /// `reporter`.
+    pub fn load(
+        path: &Path,
+        repository: Option<&Path>,
+        reporter: &mut dyn Reporter,
+    ) -> anyhow::Result<Self> {
+        let mut config = match repository {
+            // In recursive mode, the root configuration is optional.
+            Some(_) if !Self::exists(path)? => Self::default(),
+            _ => Self::read(path, reporter).with_exit_code(ExitCode::Config)?,
+        };
+        if let Some(repository) = repository {
+            config.include_nested(repository, path, reporter)?;
+        }
+        // Overrides apply to nested checks as well.
+        config.apply_local(path).with_exit_code(ExitCode::Config)?;
         config
-            .download_bundle()
-            .context("Failed to download bundle")?;
+            .download_bundle(reporter)
+            .context("Failed to download bundle")
+            .with_exit_code(ExitCode::Bundle)?;
 
         Ok(config)
     }
+    /// Read, validate and resolve the configuration, without downloading the bundle.
+    pub fn read(path: &Path, reporter: &mut dyn Reporter) -> anyhow::Result<Self> {
+        let mut config = Self::parse_root(path)?.resolve(path, reporter)?;
+        config.checks = crate::matrix::expand(config.checks);
+        crate::validate::unique_names(&config.checks)?;
+        crate::variables::validate(&config.checks)?;
+        Ok(config)
+    }
+    /// Read and validate a single configuration file.
+    pub fn parse(path: &Path) -> anyhow::Result<Self> {
+        let config = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to open configuration at {:?}", path))?;
+        Ok(Self::from_source(path, &config)?)
+    }
+    /// Like [`Config::parse`], falling back to the `[workspace.metadata.checkalot]` or
+    /// `[package.metadata.checkalot]` table of the `Cargo.toml` next to `path` if it does not exist.
+    pub fn parse_root(path: &Path) -> anyhow::Result<Self> {
+        if path.exists() {
+            return Self::parse(path);
+        }
+        let manifest_path = path.with_file_name("Cargo.toml");
+        Self::from_manifest(&manifest_path)?.with_context(|| {
+            format!(
+                "Failed to find a configuration at {:?}, nor in the metadata of {:?}",
+                path, manifest_path
+            )
+        })
+    }
+    /// Whether [`Config::parse_root`] would find a configuration. Fails if the `Cargo.toml` is
+    /// invalid.
+    pub fn exists(path: &Path) -> anyhow::Result<bool> {
+        Ok(path.exists() || Self::from_manifest(&path.with_file_name("Cargo.toml"))?.is_some())
+    }
+    /// Read the configuration from the metadata of a `Cargo.toml`, if present.
+    fn from_manifest(path: &Path) -> anyhow::Result<Option<Self>> {
+        #[derive(Deserialize)]
+        struct Manifest<T> {
+            workspace: Option<Section<T>>,
+            package: Option<Section<T>>,
+        }
+        #[derive(Deserialize)]
+        struct Section<T> {
+            metadata: Option<Metadata<T>>,
+        }
+        #[derive(Deserialize)]
+        struct Metadata<T> {
+            checkalot: Option<T>,
+        }
+        impl<T> Manifest<T> {
+            fn checkalot(self) -> Option<T> {
+                [self.workspace, self.package]
+                    .into_iter()
+                    .flatten()
+                    .find_map(|s| s.metadata?.checkalot)
+            }
+        }
+        /// Locations of the checks and of their keys.
+        #[derive(Deserialize)]
+        struct Spans {
+            #[serde(default)]
+            checks: Vec<toml::Spanned<BTreeMap<toml::Spanned<String>, toml::Value>>>,
+        }
+        if !path.exists() {
+            return Ok(None);
+        }
+        let text =
+            std::fs::read_to_string(path).with_context(|| format!("Failed to open {:?}", path))?;
+        let source = Source::new(path, &text);
+        let manifest: Manifest<Config> = toml::from_str(&text)
+            .map_err(|e| source.error(vec![source.toml_error(&e)]))
+            .with_context(|| format!("Failed to parse configuration in {:?}", path))?;
+        let Some(config) = manifest.checkalot() else {
+            return Ok(None);
+        };
+        let spans = toml::from_str::<Manifest<Spans>>(&text)?
+            .checkalot()
+            .map(|spans| {
+                spans
+                    .checks
+                    .iter()
+                    .map(|check| {
+                        let keys = check.get_ref().keys();
+                        let keys = keys.map(|k| (k.get_ref().clone(), k.span())).collect();
+                        (check.span(), keys)
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let source = source.with_checks(spans);
+        let diagnostics = config.validate(&source);
+        if !diagnostics.is_empty() {
+            return Err(source.error(diagnostics).into());
+        }
+        Ok(Some(config))
+    }
+    pub fn from_source(path: &Path, config: &str) -> Result<Self, Diagnostics> {
+        let source = Source::new(path, config);
+        let config: Config =
+            serde_yaml::from_str(config).map_err(|e| source.error(vec![source.yaml_error(&e)]))?;
+        let diagnostics = config.validate(&source);
+        if !diagnostics.is_empty() {
+            return Err(source.error(diagnostics));
+        }
+        Ok(config)
+    }
 
-    pub fn filter(&mut self, only: HashSet<&str>, skip: HashSet<&str>) -> anyhow::Result<()> {
-        let checks: HashSet<_> = self.checks.iter().map(Check::name).collect();
-        anyhow::ensure!(
-            skip.is_subset(&checks),
-            "The --skip checks are not a subset of available checks {:?}",
-            checks
-        );
-        anyhow::ensure!(
-            only.is_subset(&checks),
-            "The --only checks are not a subset of available checks {:?}",
-            checks
-        );
-        if !only.is_empty() {
-            println!("{} {:?}", "Executing only".yellow(), only);
-            self.checks.retain(|c| only.contains(&c.name()));
-        }
-        if !skip.is_empty() {
-            self.checks.retain(|c| !skip.contains(&c.name()));
-            println!(" {} {:?}", "Skipping".yellow(), skip);
+    /// Add the checks of a profile, which is removed from the configuration.
+    pub fn take_profile(&mut self, name: &str) -> anyhow::Result<Profile> {
+        let Some(mut profile) = self.profiles.remove(name) else {
+            let help = crate::validate::suggest(name, self.profiles.keys().map(String::as_str))
+                .map(|s| format!(" ({})", s))
+                .unwrap_or_default();
+            return Err(anyhow::anyhow!("Unknown profile `{}`{}", name, help))
+                .with_exit_code(ExitCode::Usage);
+        };
+        let checks = std::mem::take(&mut profile.checks);
+        let checks = self.apply_templates(checks)?;
+        let mut checks = crate::presets::expand(checks);
+        for check in &mut checks {
+            check.origin = format!("profile {}", name);
+        }
+        let checks = crate::matrix::expand(checks);
+        crate::validate::unique_names(&checks).with_exit_code(ExitCode::Config)?;
+        crate::variables::validate(&checks).with_exit_code(ExitCode::Config)?;
+        crate::extends::merge(&mut self.checks, checks);
+        Ok(profile)
+    }
+    /// Restrict the checks to the ones kept by all the selections, e.g. of a profile and of the
+    /// command line. The names are looked up among all the checks.
+    pub fn filter(&mut self, selections: &[Selection]) -> anyhow::Result<()> {
+        let checks: HashSet<_> = self.checks.iter().map(CheckEntry::name).collect();
+        for selection in selections {
+            for (field, names) in [("skip", &selection.skip), ("only", &selection.only)] {
+                if let Some(name) = names.iter().find(|n| !checks.contains(*n)) {
+                    let help = crate::validate::suggest(name, checks.iter().copied())
+                        .map(|s| format!(" ({})", s))
+                        .unwrap_or_default();
+                    let source = match selection.profile {
+                        Some(profile) => format!("`{}` of profile `{}`", field, profile),
+                        None => format!("--{}", field),
+                    };
+                    return Err(anyhow::anyhow!(
+                        "Unknown check `{}` in {}{}",
+                        name,
+                        source,
+                        help
+                    ))
+                    .with_exit_code(ExitCode::Usage);
+                }
+            }
+            for selector in &selection.select {
+                selector
+                    .validate(&self.checks)
+                    .with_exit_code(ExitCode::Usage)?;
+            }
+        }
+        for selection in selections {
+            if !selection.only.is_empty() {
+                println!("{} {:?}", "Executing only".yellow(), selection.only);
+                self.checks.retain(|c| selection.only.contains(&c.name()));
+            }
+            if !selection.skip.is_empty() {
+                self.checks.retain(|c| !selection.skip.contains(&c.name()));
+                println!(" {} {:?}", "Skipping".yellow(), selection.skip);
+            }
+            for selector in &selection.select {
+                println!("{} {}", "Selecting".yellow(), selector);
+                self.checks.retain(|c| selector.matches(c));
+            }
         }
         Ok(())
     }
 }
 
-#[derive(serde::Deserialize, serde::Serialize, Debug)]
-#[serde(untagged)]
+/// Checks selected by a profile or on the command line.
+pub struct Selection<'a> {
+    /// Profile of the selection, if not from the command line.
+    pub profile: Option<&'a str>,
+    pub only: HashSet<&'a str>,
+    pub skip: HashSet<&'a str>,
+    pub select: Vec<Selector>,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, JsonSchema)]
+#[serde(untagged, deny_unknown_fields)]
 pub enum CommandSpec {
+    /// Command, succeeding with a status code of 0.
     Simple(String),
     Complex {
         command: String,
+        /// Status codes denoting success.
         success_statuses: Vec<i32>,
     },
 }
@@ -123,32 +372,187 @@ impl CommandSpec {
     }
 }
 
-#[derive(serde::Deserialize, serde::Serialize, Debug)]
-#[serde(rename_all = "lowercase", tag = "type")]
+/// A check, with the options common to all types of checks.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, JsonSchema)]
+pub struct CheckEntry {
+    #[serde(flatten)]
+    pub check: Check,
+    /// Tags, to select checks with `--select`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Only run the check if this condition holds; otherwise, it is reported as skipped.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "serde_yaml::with::singleton_map_recursive"
+    )]
+    #[schemars(with = "Option<crate::condition::Condition>")]
+    pub when: Option<crate::condition::Condition>,
+    /// Folder of the configuration defining the check, relative to the repository root.
+    /// Empty except for nested configurations found with `--recursive`.
+    #[serde(skip)]
+    pub(crate) folder: PathBuf,
+    /// Name prefixed with `folder`.
+    #[serde(skip)]
+    pub(crate) qualified_name: Option<String>,
+    /// Configuration file defining the check.
+    #[serde(skip)]
+    pub(crate) origin: String,
+    /// Settings changed by local configurations, with their file.
+    #[serde(skip)]
+    pub(crate) overridden: Vec<(String, String)>,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, JsonSchema)]
+#[serde(rename_all = "lowercase", tag = "type", deny_unknown_fields)]
 #[allow(clippy::large_enum_variant)]
 pub enum Check {
+    /// Checks that the version of checkalot meets a requirement.
     Version {
+        #[schemars(with = "String")]
         version: semver::VersionReq,
     },
     /// Checks if the repository is clean. Untracked files are ignored.
     #[serde(rename = "git-is-clean")]
-    GitClean,
-    /// Check if the repository is rebased on origin/master.
+    GitClean {},
+    /// Check if the repository is rebased on a base branch. The fix runs `git rebase`, which is
+    /// aborted in case of conflicts.
     #[serde(rename = "git-is-rebased")]
-    GitRebased,
+    GitRebased {
+        /// Base revision, e.g. `origin/main`. Defaults to the upstream branch of the current
+        /// branch, or else to the default branch of `remote`.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        base: Option<String>,
+        /// Remote to fetch. Defaults to `origin`.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        remote: Option<String>,
+        /// Fetch the remote before checking. Disable for offline use.
+        #[serde(default = "default_true", skip_serializing_if = "is_true")]
+        fetch: bool,
+    },
+    /// Checks the messages of the commits since the merge base with a base branch.
+    #[serde(rename = "commit-messages")]
+    CommitMessages(crate::commits::CommitMessages),
+    /// Checks the files for conflict markers, large files, binaries, forbidden paths and
+    /// names differing only by case.
+    Files(crate::files::FilesCheck),
+    /// Checks trailing whitespace, final newlines, line endings, tabs in YAML and byte order
+    /// marks. The fix rewrites the files.
+    Whitespace(crate::whitespace::Whitespace),
+    /// Checks that the files start with a license header. The fix inserts or updates it.
+    #[serde(rename = "license-header")]
+    LicenseHeader(crate::license::LicenseHeader),
+    /// Checks rules forbidding or requiring regular expressions in the files.
+    Pattern(crate::pattern::Pattern),
+    /// Checks the files for private keys, tokens and high-entropy strings.
+    Secrets(crate::secrets::Secrets),
+    /// Checks that TODO, FIXME, XXX and HACK comments reference an issue.
+    Todos(crate::todos::Todos),
+    /// Checks the syntax of YAML, JSON and TOML files, and validates them against JSON Schemas.
+    Syntax(crate::syntax::Syntax),
+    /// Built-in group of checks, shipped with checkalot.
+    Preset(crate::presets::Preset),
+    /// Custom command. Variables such as `{repo}`, `{check}`, `{git.sha}` or `${ENV}` are
+    /// replaced in the commands, `folder` and `output`.
     Command {
+        /// Name, used for display and for selecting checks.
         name: String,
         /// Command to execute; a status code of 0 denotes success.
-        command: CommandSpec,
+        /// Required unless the check uses a template.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        command: Option<CommandSpec>,
+        /// Template providing the fields that are not set.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        uses: Option<String>,
+        /// Parameters of the template.
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        with: BTreeMap<String, String>,
         /// Command to attempt to fix failures.
+        #[serde(skip_serializing_if = "Option::is_none")]
         fix_command: Option<CommandSpec>,
         /// Directory where the command should be executed. Repository root if left empty.
+        #[serde(skip_serializing_if = "Option::is_none")]
         folder: Option<PathBuf>,
         /// Command that produces a version number to be checked against `version`.
+        #[serde(skip_serializing_if = "Option::is_none")]
         version_command: Option<CommandSpec>,
         /// Semver requirement on the tool.
+        #[schemars(with = "Option<String>")]
+        #[serde(skip_serializing_if = "Option::is_none")]
         version: Option<semver::VersionReq>,
+        /// Glob patterns, relative to the configuration folder, of the files concerned by the
+        /// command. Unlike the `.gitignore` patterns of the built-in checks, `*` also matches `/`,
+        /// e.g. `*.md` matches `docs/index.md`.
+        /// With `--changed-since`, the check is skipped if none of these files changed.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        paths: Vec<String>,
+        /// Named axes, expanding the check into one check per combination of their values.
+        /// `{axis}` placeholders are replaced in all the fields, and quoted in the commands.
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        matrix: BTreeMap<String, Vec<String>>,
         /// Save stderr and stdout at this location, overwriting if the file exists.
+        /// Missing parent folders are created.
+        #[serde(skip_serializing_if = "Option::is_none")]
         output: Option<PathBuf>,
+        /// Environment variables set for the commands.
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        env: BTreeMap<String, String>,
+        /// Duration in seconds after which the commands are killed.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        timeout: Option<u64>,
     },
 }
+
+pub(crate) fn default_true() -> bool {
+    true
+}
+pub(crate) fn is_true(b: &bool) -> bool {
+    *b
+}
+
+#[test]
+fn schema_up_to_date() {
+    assert_eq!(
+        include_str!("../checkalot.schema.json"),
+        Config::schema(),
+        "Regenerate the schema with `checkalot schema > checkalot.schema.json`"
+    );
+}
+
+#[test]
+fn filter_selections() {
+    let mut config: Config = serde_yaml::from_str(
+        "
+checks:
+  - type: command
+    name: fmt
+    command: cargo fmt
+  - type: command
+    name: clippy
+    command: cargo clippy
+",
+    )
+    .unwrap();
+    let selection = |profile, only: &[&'static str], skip: &[&'static str]| Selection {
+        profile,
+        only: only.iter().copied().collect(),
+        skip: skip.iter().copied().collect(),
+        select: vec![],
+    };
+    // Checks skipped by the profile can be named on the command line.
+    config
+        .filter(&[
+            selection(Some("ci"), &[], &["fmt"]),
+            selection(None, &["fmt", "clippy"], &[]),
+        ])
+        .unwrap();
+    let names: Vec<_> = config.checks.iter().map(CheckEntry::name).collect();
+    assert_eq!(names, ["clippy"]);
+    let error = config
+        .filter(&[selection(Some("ci"), &[], &["clipy"])])
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Unknown check `clipy` in `skip` of profile `ci` (did you mean `clippy`?)"
+    );
+}
@@ -1,17 +1,27 @@
 use std::path::PathBuf;
 
-use colored::Colorize;
-
 #[derive(thiserror::Error, Debug)]
 pub enum RunCommandError {
     #[error("Executable `{0}` not found. Is it installed and present in the PATH?")]
     NotFound(String),
     #[error("Command terminated with a failure status code {code}")]
     StatusCode { output: String, code: i32 },
+    #[error("`{command}` terminated with a failure status code {code}:\n{output}")]
+    ToolFailed {
+        command: String,
+        output: String,
+        code: i32,
+    },
     #[error("Command was terminated by a signal")]
     Signal,
+    #[error("Command timed out after {0:?}")]
+    Timeout(std::time::Duration),
     #[error("Command produced non-UTF-8 output")]
     Utf8,
+    #[error("Unexpected output of `{command}`: {output:?}")]
+    UnexpectedOutput { command: String, output: String },
+    #[error("{0}")]
+    Variable(String),
     #[error("Could not parse command: {0}")]
     Split(#[from] shell_words::ParseError),
     #[error("Other execution error: {0}")]
@@ -31,6 +41,10 @@ pub enum CheckError {
     // Versions
     #[error("A `version_command` is required to check against `version`")]
     MissingVersionCommand,
+    #[error("A `command` is required, unless the check uses a template")]
+    MissingCommand,
+    #[error("The preset `{0}` cannot be executed before being expanded into its checks")]
+    UnexpandedPreset(String),
     #[error("Version {version} does not meet requirement {version_req}")]
     VersionReq {
         version_req: semver::VersionReq,
@@ -41,14 +55,220 @@ pub enum CheckError {
     // Build-in check errors
     #[error("Repository is dirty")]
     DirtyRepository,
-    #[error("The commit {local} is not rebased on origin/master ({origin})")]
-    NotRebased { local: String, origin: String },
+    #[error("The branch is {behind} commit(s) behind {base}")]
+    NotRebased { base: String, behind: usize },
+    #[error("Failed to rebase on {base}, the rebase was aborted:\n{output}")]
+    RebaseFailed { base: String, output: String },
+    #[error("Failed to find the base branch: the current branch has no upstream, and the remote `{0}` has no HEAD. Set `base`, or run `git remote set-head {0} --auto`.")]
+    NoBase(String),
+    #[error("Failed to find the base branch: the remote `{0}` has no HEAD. Set `base`, or run `git remote set-head {0} --auto`.")]
+    NoRemoteHead(String),
+    #[error("{} problem(s) found:\n{}", .0.len(), display_findings(.0))]
+    Findings(Vec<Finding>),
+    #[error("Invalid regular expression: {0}")]
+    Regex(#[from] regex::Error),
+    #[error("Invalid pattern: {0}")]
+    Pattern(#[from] ignore::Error),
+    #[error("Invalid license header: {0}")]
+    InvalidHeader(String),
+    #[error("Invalid JSON Schema {0:?}: {1}")]
+    InvalidSchema(PathBuf, String),
+    #[error("Failed to read {0:?}: {1}")]
+    ReadFile(PathBuf, std::io::Error),
+}
+
+/// Problem reported by a built-in check.
+#[derive(serde::Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    /// File relative to the repository, or commit.
+    pub location: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column: Option<usize>,
+    pub message: String,
+}
+impl std::fmt::Display for Finding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.location)?;
+        for n in [self.line, self.column].into_iter().flatten() {
+            write!(f, ":{}", n)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+fn display_findings(findings: &[Finding]) -> String {
+    findings
+        .iter()
+        .map(|f| format!("  {}", f))
+        .collect::<Vec<_>>()
+        .join("\n")
 }
 impl CheckError {
-    pub fn print(&self) {
-        println!("\n{}", self.to_string().red());
-        if let CheckError::RunCommand(RunCommandError::StatusCode { output, .. }) = &self {
-            println!("{}", output);
+    pub fn exit_code(&self) -> ExitCode {
+        match self {
+            CheckError::RunCommand(e) => e.exit_code(),
+            CheckError::NoFix
+            | CheckError::DirtyRepository
+            | CheckError::NotRebased { .. }
+            | CheckError::RebaseFailed { .. }
+            | CheckError::Findings(_) => ExitCode::CheckFailed,
+            CheckError::ExecutionFolder(_)
+            | CheckError::Regex(_)
+            | CheckError::Pattern(_)
+            | CheckError::InvalidHeader(_)
+            | CheckError::InvalidSchema(..)
+            | CheckError::NoBase(_)
+            | CheckError::NoRemoteHead(_)
+            | CheckError::MissingVersionCommand
+            | CheckError::MissingCommand
+            | CheckError::UnexpandedPreset(_) => ExitCode::Config,
+            CheckError::VersionReq { .. } | CheckError::VersionFind(_) => ExitCode::ToolVersion,
+            CheckError::WriteOutput(_) | CheckError::ReadFile(..) => ExitCode::Other,
         }
     }
 }
+impl RunCommandError {
+    /// Failure of a command run by checkalot itself, e.g. git, rather than of a checked command.
+    pub(crate) fn tool(self, command: impl Into<String>) -> Self {
+        match self {
+            RunCommandError::StatusCode { output, code } => RunCommandError::ToolFailed {
+                command: command.into(),
+                output: output.trim_end().into(),
+                code,
+            },
+            e => e,
+        }
+    }
+    pub fn exit_code(&self) -> ExitCode {
+        match self {
+            RunCommandError::NotFound(_) => ExitCode::MissingTool,
+            RunCommandError::StatusCode { .. } => ExitCode::CheckFailed,
+            RunCommandError::ToolFailed { .. } | RunCommandError::Timeout(_) => {
+                ExitCode::Environment
+            }
+            RunCommandError::Split(_) | RunCommandError::Variable(_) => ExitCode::Config,
+            RunCommandError::Signal
+            | RunCommandError::Utf8
+            | RunCommandError::UnexpectedOutput { .. }
+            | RunCommandError::Other(_) => ExitCode::Other,
+        }
+    }
+}
+
+/// Exit codes of the process, by failure category. The code 2 is used by the argument parser
+/// for invalid command line arguments.
+///
+/// Can be attached to an [`anyhow::Error`] to categorize it with [`WithExitCode`], see
+/// [`exit_code`].
+#[derive(thiserror::Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitCode {
+    /// A check failed, e.g. because of lint errors.
+    #[error("Check failed")]
+    CheckFailed = 1,
+    /// The configuration could not be read or is invalid.
+    #[error("Invalid configuration")]
+    Config = 3,
+    /// The bundle could not be downloaded or has a mismatching checksum.
+    #[error("Failed to set up bundle")]
+    Bundle = 4,
+    /// A tool required by a check is not installed.
+    #[error("Missing tool")]
+    MissingTool = 5,
+    /// A tool (or checkalot itself) does not meet the version requirement.
+    #[error("Version requirement not met")]
+    ToolVersion = 6,
+    /// Any other error, e.g. a command terminated by a signal.
+    #[error("Unexpected error")]
+    Other = 7,
+    /// Selection of unknown checks or profiles, or invalid selection expression.
+    #[error("Invalid usage")]
+    Usage = 8,
+    /// A command run by checkalot (e.g. git) failed, or a check timed out.
+    #[error("Environment failure")]
+    Environment = 9,
+}
+
+/// Error categorized with an exit code, displayed as the underlying error.
+#[derive(Debug)]
+pub struct Categorized {
+    code: ExitCode,
+    error: anyhow::Error,
+}
+impl std::fmt::Display for Categorized {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.error)
+    }
+}
+impl std::error::Error for Categorized {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.error.source()
+    }
+}
+
+/// Attach an exit code to errors, without changing their message.
+pub trait WithExitCode<T> {
+    fn with_exit_code(self, code: ExitCode) -> anyhow::Result<T>;
+}
+impl<T, E: Into<anyhow::Error>> WithExitCode<T> for Result<T, E> {
+    fn with_exit_code(self, code: ExitCode) -> anyhow::Result<T> {
+        self.map_err(|e| {
+            anyhow::Error::new(Categorized {
+                code,
+                error: e.into(),
+            })
+        })
+    }
+}
+
+/// Deduce the exit code from the outermost category attached to the error, or from the
+/// underlying [`CheckError`] or [`RunCommandError`].
+pub fn exit_code(error: &anyhow::Error) -> ExitCode {
+    error
+        .chain()
+        .find_map(|e| {
+            if let Some(e) = e.downcast_ref::<Categorized>() {
+                Some(e.code)
+            } else if let Some(e) = e.downcast_ref::<CheckError>() {
+                Some(e.exit_code())
+            } else {
+                e.downcast_ref::<RunCommandError>()
+                    .map(RunCommandError::exit_code)
+            }
+        })
+        .unwrap_or(ExitCode::Other)
+}
+
+#[test]
+fn categorize_errors() {
+    use anyhow::Context;
+
+    let error = anyhow::Error::new(CheckError::RunCommand(RunCommandError::NotFound(
+        "cargo-deny".into(),
+    )))
+    .context("The check 'deny' has failed");
+    assert_eq!(exit_code(&error), ExitCode::MissingTool);
+
+    let error: anyhow::Result<()> = Err(anyhow::anyhow!("Failed to deserialize configuration"));
+    let error = error
+        .context("Invalid field")
+        .with_exit_code(ExitCode::Config)
+        .unwrap_err();
+    assert_eq!(exit_code(&error), ExitCode::Config);
+    // The message is kept, with its causes.
+    assert_eq!(
+        format!("{:#}", error),
+        "Invalid field: Failed to deserialize configuration"
+    );
+
+    let error = anyhow::Error::new(CheckError::RunCommand(
+        RunCommandError::StatusCode {
+            output: "fatal: unable to access".into(),
+            code: 128,
+        }
+        .tool("git fetch origin"),
+    ));
+    assert_eq!(exit_code(&error), ExitCode::Environment);
+
+    assert_eq!(exit_code(&anyhow::anyhow!("other")), ExitCode::Other);
+}
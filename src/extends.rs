@@ -0,0 +1,178 @@
+//! Inheritance of checks from other configurations, with `extends`.
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+
+use crate::ui::Reporter;
+use crate::validate::suggest;
+use crate::{CheckEntry, Config, Extends};
+
+impl Config {
+    /// Include the checks and profiles of the configurations listed in `extends`, recursively,
+    /// and apply `remove`. `path` is the location of this configuration. Downloads of remote
+    /// configurations are reported to `reporter`.
+    pub fn resolve(self, path: &Path, reporter: &mut dyn Reporter) -> anyhow::Result<Self> {
+        let mut stack = vec![canonical(path)];
+        self.resolve_impl(
+            Some(path),
+            &path.display().to_string(),
+            &mut stack,
+            reporter,
+        )
+    }
+    /// `path` is `None` for remote configurations. `origin` describes the configuration.
+    fn resolve_impl(
+        mut self,
+        path: Option<&Path>,
+        origin: &str,
+        stack: &mut Vec<PathBuf>,
+        reporter: &mut dyn Reporter,
+    ) -> anyhow::Result<Self> {
+        let mut checks = vec![];
+        let mut bundle = None;
+        let mut profiles = BTreeMap::new();
+        let mut templates = BTreeMap::new();
+        for extends in std::mem::take(&mut self.extends) {
+            let (parent_path, remote) = match &extends {
+                Extends::Path(relative) => {
+                    let path = path.with_context(|| {
+                        format!(
+                            "Remote configurations cannot extend local paths ({:?})",
+                            relative
+                        )
+                    })?;
+                    (path.parent().unwrap_or(Path::new("")).join(relative), false)
+                }
+                Extends::Url { url, sha256 } => (fetch(url, sha256, reporter)?, true),
+            };
+            let parent_canonical = canonical(&parent_path);
+            anyhow::ensure!(
+                !stack.contains(&parent_canonical),
+                "Cycle in `extends` through {:?}",
+                parent_path
+            );
+            stack.push(parent_canonical);
+            let parent_origin = match &extends {
+                Extends::Path(_) => parent_path.display().to_string(),
+                Extends::Url { url, .. } => url.clone(),
+            };
+            let parent = Config::parse(&parent_path)
+                .and_then(|c| {
+                    c.resolve_impl(
+                        (!remote).then_some(&parent_path),
+                        &parent_origin,
+                        stack,
+                        reporter,
+                    )
+                })
+                .with_context(|| format!("Failed to extend {}", extends))?;
+            stack.pop();
+
+            merge(&mut checks, parent.checks);
+            bundle = parent.bundle.or(bundle);
+            profiles.extend(parent.profiles);
+            templates.extend(parent.templates);
+        }
+        templates.append(&mut self.templates);
+        self.templates = templates;
+        let own = std::mem::take(&mut self.checks);
+        let own = self.apply_templates(own)?;
+        let mut own = crate::presets::expand(own);
+        for check in &mut own {
+            check.origin = origin.into();
+        }
+        crate::validate::unique_names(&own)?;
+        merge(&mut checks, own);
+        remove(&mut checks, &std::mem::take(&mut self.remove))?;
+        self.checks = checks;
+        self.bundle = self.bundle.or(bundle);
+        profiles.append(&mut self.profiles);
+        self.profiles = profiles;
+        Ok(self)
+    }
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.into())
+}
+
+/// Download a remote configuration to the cache, returning its path.
+fn fetch(url: &str, sha256: &str, reporter: &mut dyn Reporter) -> anyhow::Result<PathBuf> {
+    let folder = crate::cache_dir()?.join("extends");
+    let path = folder.join(sha256).with_extension("yaml");
+    if !path.exists() {
+        reporter.info(&format!("Downloading configuration from {}...", url));
+        let mut file = crate::download(url, sha256)
+            .with_context(|| format!("Failed to download configuration from {}", url))?;
+        std::fs::create_dir_all(&folder)?;
+        // Write atomically, so that an interrupted download is not considered valid.
+        let mut tempfile = tempfile::NamedTempFile::new_in(&folder)?;
+        std::io::copy(&mut file, &mut tempfile)?;
+        tempfile.persist(&path)?;
+    }
+    Ok(path)
+}
+
+/// Add checks, replacing existing ones with the same name.
+pub(crate) fn merge(checks: &mut Vec<CheckEntry>, overrides: Vec<CheckEntry>) {
+    for check in overrides {
+        if let Some(existing) = checks.iter_mut().find(|c| c.name() == check.name()) {
+            *existing = check;
+        } else {
+            checks.push(check);
+        }
+    }
+}
+
+/// Remove checks by name, failing if one does not exist.
+pub(crate) fn remove(checks: &mut Vec<CheckEntry>, names: &[String]) -> anyhow::Result<()> {
+    for name in names {
+        if !checks.iter().any(|c| c.name() == name) {
+            let help = suggest(name, checks.iter().map(CheckEntry::name))
+                .map(|s| format!(" ({})", s))
+                .unwrap_or_default();
+            anyhow::bail!("Cannot remove unknown check `{}`{}", name, help);
+        }
+        checks.retain(|c| c.name() != name);
+    }
+    Ok(())
+}
+
+#[test]
+fn merge_and_remove() {
+    let parse = |s: &str| Config::from_source(Path::new("checkalot.yaml"), s).unwrap();
+    let base = parse(
+        "checks:
+  - type: git-is-clean
+  - type: command
+    name: fmt
+    command: cargo fmt --check
+  - type: command
+    name: clippy
+    command: cargo clippy",
+    );
+    let mut checks = base.checks;
+    merge(
+        &mut checks,
+        parse(
+            "checks:
+  - type: command
+    name: fmt
+    command: cargo fmt --all --check
+  - type: command
+    name: deny
+    command: cargo deny check",
+        )
+        .checks,
+    );
+    remove(&mut checks, &["git-is-clean".into()]).unwrap();
+    assert_eq!(
+        checks.iter().map(CheckEntry::name).collect::<Vec<_>>(),
+        ["fmt", "clippy", "deny"]
+    );
+    assert!(
+        matches!(&checks[0].check, crate::Check::Command { command, .. } if command.as_ref().unwrap().command() == "cargo fmt --all --check")
+    );
+    assert!(remove(&mut checks, &["fmtt".into()]).is_err());
+}
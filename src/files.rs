@@ -0,0 +1,247 @@
+//! Built-in `files` check, finding files that should not be committed.
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::errors::{CheckError, Finding};
+use crate::scan::{self, File, Patterns, Scope};
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct FilesCheck {
+    /// Files to scan.
+    #[serde(default, skip_serializing_if = "Scope::is_default")]
+    scope: Scope,
+    /// Patterns (in the `.gitignore` syntax) of the files to scan. All files if empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    include: Vec<String>,
+    /// Patterns (in the `.gitignore` syntax) of the files to ignore.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    exclude: Vec<String>,
+    /// Report leftover merge conflict markers.
+    #[serde(
+        default = "crate::config::default_true",
+        skip_serializing_if = "crate::config::is_true"
+    )]
+    conflict_markers: bool,
+    /// Maximal size of the files, in KiB. Set to `null` to allow any size.
+    #[serde(default = "default_max_size_kib")]
+    max_size_kib: Option<u64>,
+    /// Patterns (in the `.gitignore` syntax) of the binary files that are allowed, e.g.
+    /// `*.png`. Other binary files are reported.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    allowed_binaries: Vec<String>,
+    /// Patterns (in the `.gitignore` syntax) of files that must not be committed, e.g. `*.pem`
+    /// or `target/`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    forbidden: Vec<String>,
+    /// Report paths differing only by case, which conflict on case-insensitive file systems.
+    /// The paths and their parent folders are compared with all the files of the git index.
+    #[serde(
+        default = "crate::config::default_true",
+        skip_serializing_if = "crate::config::is_true"
+    )]
+    case_collisions: bool,
+}
+
+fn default_max_size_kib() -> Option<u64> {
+    Some(1024)
+}
+
+/// Lines of merge conflict markers. The separator is only reported after an opening marker, as
+/// it is also used e.g. for Markdown headings.
+fn conflict_markers(content: &str) -> Vec<usize> {
+    let is_marker = |line: &str, marker: &str| {
+        line.strip_prefix(marker)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with(' '))
+    };
+    let mut open = false;
+    let mut lines = vec![];
+    for (i, line) in content.lines().enumerate() {
+        let line = line.trim_end_matches('\r');
+        let marker = if is_marker(line, "<<<<<<<") {
+            open = true;
+            true
+        } else if is_marker(line, ">>>>>>>") {
+            open = false;
+            true
+        } else {
+            open && (line == "=======" || is_marker(line, "|||||||"))
+        };
+        if marker {
+            lines.push(i + 1);
+        }
+    }
+    lines
+}
+
+/// The path and its parent folders, e.g. `docs`, `docs/guide` and `docs/guide/a.md`.
+fn ancestors(path: &Path) -> Vec<String> {
+    let mut ancestors: Vec<String> = vec![];
+    for component in path.components() {
+        let component = component.as_os_str().to_string_lossy();
+        ancestors.push(match ancestors.last() {
+            Some(parent) => format!("{}/{}", parent, component),
+            None => component.into_owned(),
+        });
+    }
+    ancestors
+}
+
+/// Report the files whose path, or one of its parent folders, differs only by case from another
+/// path of the git index (e.g. a staged `readme.md` next to a tracked `README.md`, or `Docs/a`
+/// next to `docs/b`) or of the listed files.
+fn case_collisions(repository: &Path, files: &[File]) -> Result<Vec<Finding>, CheckError> {
+    let index = crate::git::git(repository, &["ls-files", "-z"])?;
+    let mut lowercase: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    let paths = index
+        .split('\0')
+        .filter(|p| !p.is_empty())
+        .map(Path::new)
+        .chain(files.iter().map(|f| f.path.as_path()));
+    for path in paths {
+        for ancestor in ancestors(path) {
+            lowercase
+                .entry(ancestor.to_lowercase())
+                .or_default()
+                .insert(ancestor);
+        }
+    }
+    let mut findings = vec![];
+    for file in files {
+        let ancestors = ancestors(&file.path);
+        // Only the topmost colliding folder is reported.
+        for (i, ancestor) in ancestors.iter().enumerate() {
+            let others: Vec<_> = lowercase[&ancestor.to_lowercase()]
+                .iter()
+                .filter(|other| *other != ancestor)
+                .map(String::as_str)
+                .collect();
+            if others.is_empty() {
+                continue;
+            }
+            let message = if i + 1 == ancestors.len() {
+                format!("differs only by case from {}", others.join(", "))
+            } else {
+                format!(
+                    "folder {} differs only by case from {}",
+                    ancestor,
+                    others.join(", ")
+                )
+            };
+            findings.push(file.finding(None, message));
+            break;
+        }
+    }
+    Ok(findings)
+}
+
+impl FilesCheck {
+    fn scan(
+        &self,
+        file: &File,
+        content: &[u8],
+        allowed_binaries: &Patterns,
+        forbidden: &Patterns,
+    ) -> Vec<Finding> {
+        let mut findings = vec![];
+        if forbidden.is_match(&file.path) {
+            findings.push(file.finding(None, "forbidden path"));
+        }
+        let size = content.len() as u64;
+        if let Some(max) = self.max_size_kib.filter(|max| size > max * 1024) {
+            findings.push(file.finding(
+                None,
+                format!("{:.1} KiB, larger than {} KiB", size as f64 / 1024.0, max),
+            ));
+        }
+        if scan::is_binary(content) {
+            if !allowed_binaries.is_match(&file.path) {
+                findings.push(file.finding(None, "binary file"));
+            }
+        } else if self.conflict_markers {
+            for line in conflict_markers(&String::from_utf8_lossy(content)) {
+                findings.push(file.finding(Some(line), "merge conflict marker"));
+            }
+        }
+        findings
+    }
+    pub(crate) fn execute(&self, repository: &Path, fix: bool) -> Result<(), CheckError> {
+        if fix {
+            return Err(CheckError::NoFix);
+        }
+        let allowed_binaries = Patterns::new(&self.allowed_binaries)?;
+        let forbidden = Patterns::new(&self.forbidden)?;
+        let files = scan::list(repository, self.scope, &self.include, &self.exclude)?;
+        let mut findings = scan::scan(repository, &files, |file, content| {
+            self.scan(file, content, &allowed_binaries, &forbidden)
+        })?;
+        if self.case_collisions {
+            findings.extend(case_collisions(repository, &files)?);
+        }
+        if !findings.is_empty() {
+            return Err(CheckError::Findings(findings));
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn find_conflict_markers() {
+    assert_eq!(
+        conflict_markers(
+            "Title
+=======
+<<<<<<< HEAD
+a
+||||||| base
+=======
+b
+>>>>>>> topic
+======="
+        ),
+        [3, 5, 6, 8]
+    );
+}
+
+#[test]
+fn find_case_collisions() {
+    let folder = tempfile::tempdir().unwrap();
+    let repository = folder.path();
+    let git = |args: &[&str]| crate::git::git(repository, args).unwrap();
+    git(&["init", "-q"]);
+    git(&["config", "core.ignorecase", "false"]);
+    std::fs::create_dir_all(repository.join("Docs")).unwrap();
+    std::fs::create_dir_all(repository.join("docs")).unwrap();
+    for file in ["README.md", "Docs/a.md", "docs/b.md", "src.rs"] {
+        std::fs::write(repository.join(file), "").unwrap();
+    }
+    git(&["add", "README.md", "Docs", "src.rs"]);
+    git(&[
+        "-c",
+        "user.name=Test",
+        "-c",
+        "user.email=test@example.com",
+        "commit",
+        "-q",
+        "-m",
+        "Initial commit",
+    ]);
+    std::fs::write(repository.join("readme.md"), "").unwrap();
+    git(&["add", "readme.md", "docs"]);
+    let files = scan::list(repository, Scope::Staged, &[], &[]).unwrap();
+    let findings: Vec<_> = case_collisions(repository, &files)
+        .unwrap()
+        .iter()
+        .map(Finding::to_string)
+        .collect();
+    assert_eq!(
+        findings,
+        [
+            "docs/b.md: folder docs differs only by case from Docs",
+            "readme.md: differs only by case from README.md",
+        ]
+    );
+}
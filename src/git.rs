@@ -0,0 +1,96 @@
+//! Helpers to query the git repository.
+use std::path::{Path, PathBuf};
+
+use crate::checks::run_expr;
+use crate::errors::RunCommandError;
+
+/// Run a git command in the repository, returning its standard output. Failures are
+/// [`RunCommandError::ToolFailed`] errors, not check failures.
+pub fn git(repository: &Path, args: &[&str]) -> Result<String, RunCommandError> {
+    run_expr("git", duct::cmd("git", args).dir(repository), &[0])
+        .map_err(|e| e.tool(format!("git {}", args.join(" "))))
+}
+
+/// Upstream branch of the current branch, or else default branch of the remote.
+pub fn default_base(repository: &Path, remote: &str) -> Option<String> {
+    git(
+        repository,
+        &[
+            "rev-parse",
+            "--abbrev-ref",
+            "--symbolic-full-name",
+            "@{upstream}",
+        ],
+    )
+    .ok()
+    .map(|base| base.trim().to_owned())
+    .or_else(|| remote_head(repository, remote))
+}
+
+/// Default branch of the remote, e.g. `origin/main`.
+pub fn remote_head(repository: &Path, remote: &str) -> Option<String> {
+    git(
+        repository,
+        &[
+            "symbolic-ref",
+            "--short",
+            &format!("refs/remotes/{}/HEAD", remote),
+        ],
+    )
+    .ok()
+    .map(|base| base.trim().to_owned())
+}
+
+/// Files (relative to the repository root) that differ from the given revision, including
+/// uncommitted and untracked files. When the repository root is a sub-folder of the git
+/// repository, e.g. a cargo workspace, only the files of that folder are listed.
+pub fn changed_files(repository: &Path, since: &str) -> Result<Vec<PathBuf>, RunCommandError> {
+    // Both commands list paths relative to the current directory, unquoted.
+    let diff = git(
+        repository,
+        &["diff", "--relative", "--name-only", "-z", since],
+    )?;
+    let untracked = git(
+        repository,
+        &["ls-files", "--others", "--exclude-standard", "-z"],
+    )?;
+    Ok(diff
+        .split('\0')
+        .chain(untracked.split('\0'))
+        .filter(|f| !f.is_empty())
+        .map(PathBuf::from)
+        .collect())
+}
+
+#[test]
+fn changed_files_in_subfolder() {
+    let folder = tempfile::tempdir().unwrap();
+    let root = folder.path();
+    let git = |args: &[&str]| git(root, args).unwrap();
+    std::fs::create_dir_all(root.join("sub/src")).unwrap();
+    for file in ["top.rs", "sub/src/lib.rs"] {
+        std::fs::write(root.join(file), "").unwrap();
+    }
+    git(&["init", "-q"]);
+    git(&["add", "."]);
+    git(&[
+        "-c",
+        "user.name=Test",
+        "-c",
+        "user.email=test@example.com",
+        "commit",
+        "-q",
+        "-m",
+        "Initial commit",
+    ]);
+    for file in ["top.rs", "sub/src/lib.rs", "sub/new file.rs", "other.rs"] {
+        std::fs::write(root.join(file), "changed\n").unwrap();
+    }
+    let mut changed = changed_files(&root.join("sub"), "HEAD").unwrap();
+    changed.sort();
+    assert_eq!(
+        changed,
+        [PathBuf::from("new file.rs"), PathBuf::from("src/lib.rs")]
+    );
+    assert_eq!(changed_files(root, "HEAD").unwrap().len(), 4);
+}
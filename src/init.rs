@@ -0,0 +1,423 @@
+//! Scaffolding of a configuration with `checkalot init`, from the files found in the repository.
+use std::path::Path;
+
+use anyhow::Context;
+
+use crate::checks::{parse_version, run_command};
+use crate::errors::{ExitCode, WithExitCode};
+use crate::CommandSpec;
+
+/// Check to be written in the generated configuration.
+struct Scaffold {
+    comment: String,
+    name: &'static str,
+    command: String,
+    fix_command: Option<String>,
+    version_command: Option<&'static str>,
+    paths: &'static [&'static str],
+    /// Requirement or note, filled when probing the installed tools.
+    version: Option<Result<String, String>>,
+}
+impl Scaffold {
+    fn new(name: &'static str, comment: &str, command: impl Into<String>) -> Self {
+        Self {
+            comment: comment.into(),
+            name,
+            command: command.into(),
+            fix_command: None,
+            version_command: None,
+            paths: &[],
+            version: None,
+        }
+    }
+    fn fix(mut self, command: impl Into<String>) -> Self {
+        self.fix_command = Some(command.into());
+        self
+    }
+    fn version_command(mut self, command: &'static str) -> Self {
+        self.version_command = Some(command);
+        self
+    }
+    fn paths(mut self, paths: &'static [&'static str]) -> Self {
+        self.paths = paths;
+        self
+    }
+    /// Run the version command to require at least the installed version.
+    fn probe(&mut self, repository: &Path) {
+        let Some(command) = self.version_command else {
+            return;
+        };
+        self.version = Some(
+            match run_command(
+                &CommandSpec::Simple(command.into()),
+                repository,
+                &Default::default(),
+                None,
+                None,
+            ) {
+                Ok(out) => match parse_version(&out) {
+                    // Pre-release requirements would only match the same version.
+                    Some(v) if v.pre.is_empty() => Ok(format!(">={}", v)),
+                    _ => Err(format!("could not parse the version from `{}`", command)),
+                },
+                Err(_) => Err(format!("`{}` is not installed", command)),
+            },
+        );
+    }
+}
+
+fn read_toml(path: &Path) -> anyhow::Result<Option<toml::Table>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let text = std::fs::read_to_string(path)?;
+    Ok(Some(
+        toml::from_str(&text).with_context(|| format!("Failed to parse {:?}", path))?,
+    ))
+}
+
+/// Names of the dependencies declared in a `pyproject.toml`, in the `project` table, in PEP 735
+/// dependency groups, and in the poetry tables.
+fn python_dependencies(pyproject: &toml::Table) -> impl Iterator<Item = &str> {
+    let get = |path: &[&str]| {
+        path.iter()
+            .try_fold(pyproject, |table, key| table.get(*key)?.as_table())
+    };
+    let project = pyproject.get("project").and_then(|p| p.as_table());
+    let poetry = get(&["tool", "poetry"]);
+    // PEP 508 requirements, e.g. `ruff>=0.5; python_version >= '3.9'`.
+    let requirements = project
+        .and_then(|p| p.get("dependencies"))
+        .into_iter()
+        .chain(
+            project
+                .and_then(|p| p.get("optional-dependencies")?.as_table())
+                .into_iter()
+                .flat_map(|t| t.values()),
+        )
+        .chain(
+            get(&["dependency-groups"])
+                .into_iter()
+                .flat_map(|t| t.values()),
+        )
+        .filter_map(|v| v.as_array())
+        .flatten()
+        .filter_map(|v| v.as_str())
+        .map(|r| {
+            let end = r
+                .find(|c: char| !(c.is_ascii_alphanumeric() || "-_.".contains(c)))
+                .unwrap_or(r.len());
+            &r[..end]
+        });
+    let poetry = poetry
+        .and_then(|p| p.get("dependencies")?.as_table())
+        .into_iter()
+        .chain(poetry.and_then(|p| p.get("dev-dependencies")?.as_table()))
+        .chain(
+            poetry
+                .and_then(|p| p.get("group")?.as_table())
+                .into_iter()
+                .flat_map(|g| g.values())
+                .filter_map(|g| g.get("dependencies")?.as_table()),
+        )
+        .flat_map(|t| t.keys().map(String::as_str));
+    requirements.chain(poetry)
+}
+
+/// Detect the tools used in the repository.
+fn detect(repository: &Path) -> anyhow::Result<Vec<Scaffold>> {
+    let mut checks = vec![];
+
+    if let Some(manifest) = read_toml(&repository.join("Cargo.toml"))? {
+        let workspace = manifest.contains_key("workspace");
+        // The channel is pinned in the commands, as `RUSTUP_TOOLCHAIN` (e.g. set by
+        // `cargo +nightly checkalot`) would otherwise take precedence over the file.
+        let toolchain =
+            crate::toolchain::Toolchain::from_file(&repository.join("rust-toolchain.toml")).ok();
+        let (cargo, toolchain) = match toolchain {
+            Some(t) => (
+                format!("cargo +{}", t.0),
+                format!(", with the {} toolchain from rust-toolchain.toml", t.0),
+            ),
+            None => ("cargo".into(), String::new()),
+        };
+        let flags = if workspace {
+            " --workspace --all-targets"
+        } else {
+            " --all-targets"
+        };
+        checks.push(
+            Scaffold::new(
+                "fmt",
+                &format!("Rust formatting{}", toolchain),
+                format!("{} fmt --all -- --check", cargo),
+            )
+            .fix(format!("{} fmt --all", cargo))
+            .paths(&["**/*.rs"]),
+        );
+        checks.push(
+            Scaffold::new(
+                "clippy",
+                &format!("Rust lints{}", toolchain),
+                format!("{} clippy{} -- -D warnings", cargo, flags),
+            )
+            .fix(format!(
+                "{} clippy{} --fix --allow-dirty --allow-staged -- -D warnings",
+                cargo, flags
+            ))
+            .paths(&["**/*.rs", "**/Cargo.toml"]),
+        );
+        if workspace && repository.join(".config/hakari.toml").exists() {
+            checks.push(
+                Scaffold::new(
+                    "hakari",
+                    "Workspace-hack crate managed by cargo-hakari",
+                    "cargo hakari generate --diff",
+                )
+                .fix("cargo hakari generate")
+                .version_command("cargo hakari --version")
+                .paths(&["**/Cargo.toml"]),
+            );
+        }
+    }
+
+    let package_json = repository.join("package.json");
+    if package_json.exists() {
+        let package: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&package_json)?)
+                .with_context(|| format!("Failed to parse {:?}", package_json))?;
+        let has_dependency = |name: &str| {
+            ["dependencies", "devDependencies"]
+                .iter()
+                .any(|key| package[key].get(name).is_some())
+        };
+        if has_dependency("prettier") {
+            checks.push(
+                Scaffold::new(
+                    "prettier",
+                    "Formatting with prettier, from package.json",
+                    "npx prettier --check .",
+                )
+                .fix("npx prettier --write .")
+                .version_command("npx prettier --version"),
+            );
+        }
+        if has_dependency("eslint") {
+            checks.push(
+                Scaffold::new(
+                    "eslint",
+                    "Lints with eslint, from package.json",
+                    "npx eslint .",
+                )
+                .fix("npx eslint --fix .")
+                .version_command("npx eslint --version")
+                .paths(&["**/*.js", "**/*.jsx", "**/*.ts", "**/*.tsx"]),
+            );
+        }
+    }
+
+    let pyproject_path = repository.join("pyproject.toml");
+    if let Some(pyproject) = read_toml(&pyproject_path)? {
+        let uses = |tool: &str| {
+            pyproject.get("tool").is_some_and(|t| t.get(tool).is_some())
+                || python_dependencies(&pyproject).any(|d| d.eq_ignore_ascii_case(tool))
+        };
+        if uses("ruff") {
+            checks.push(
+                Scaffold::new(
+                    "ruff",
+                    "Python lints with ruff, from pyproject.toml",
+                    "ruff check .",
+                )
+                .fix("ruff check --fix .")
+                .version_command("ruff --version")
+                .paths(&["**/*.py", "pyproject.toml"]),
+            );
+            checks.push(
+                Scaffold::new(
+                    "ruff-format",
+                    "Python formatting with ruff",
+                    "ruff format --check .",
+                )
+                .fix("ruff format .")
+                .version_command("ruff --version")
+                .paths(&["**/*.py", "pyproject.toml"]),
+            );
+        } else if uses("black") {
+            checks.push(
+                Scaffold::new(
+                    "black",
+                    "Python formatting with black, from pyproject.toml",
+                    "black --check .",
+                )
+                .fix("black .")
+                .version_command("black --version")
+                .paths(&["**/*.py", "pyproject.toml"]),
+            );
+        }
+    }
+
+    if repository.join(".pre-commit-config.yaml").exists() {
+        checks.push(
+            Scaffold::new(
+                "pre-commit",
+                "Hooks from .pre-commit-config.yaml",
+                "pre-commit run --all-files",
+            )
+            .version_command("pre-commit --version"),
+        );
+    }
+    Ok(checks)
+}
+
+fn quote(s: &str) -> String {
+    serde_yaml::to_string(s).unwrap().trim_end().into()
+}
+
+fn render(checks: &[Scaffold]) -> String {
+    let mut out = String::from(
+        "# Configuration of checkalot, generated by `checkalot init`.
+# See https://github.com/cpg314/checkalot for all options.
+checks:
+  # Minimal version of checkalot
+  - type: version
+    version: \"",
+    );
+    out.push_str(&format!(">={}\"\n\n", env!("CARGO_PKG_VERSION")));
+    out.push_str("  # Fail if there are uncommitted changes, e.g. in CI after code generation\n");
+    out.push_str("  # - type: git-is-clean\n");
+    if checks.is_empty() {
+        out.push_str(
+            "
+  # No known tool was detected. Add custom commands, e.g.
+  # - type: command
+  #   name: tests
+  #   command: make test
+",
+        );
+    }
+    for check in checks {
+        out.push_str(&format!("\n  # {}\n", check.comment));
+        out.push_str("  - type: command\n");
+        out.push_str(&format!("    name: {}\n", check.name));
+        out.push_str(&format!("    command: {}\n", quote(&check.command)));
+        if let Some(fix_command) = &check.fix_command {
+            out.push_str(&format!("    fix_command: {}\n", quote(fix_command)));
+        }
+        if let Some(version_command) = check.version_command {
+            out.push_str(&format!(
+                "    version_command: {}\n",
+                quote(version_command)
+            ));
+            match &check.version {
+                Some(Ok(version)) => out.push_str(&format!("    version: {}\n", quote(version))),
+                Some(Err(note)) => out.push_str(&format!("    # version: {}\n", note)),
+                None => out.push_str("    # version: \">=1.0\"\n"),
+            }
+        }
+        if !check.paths.is_empty() {
+            let paths: Vec<_> = check.paths.iter().map(|p| quote(p)).collect();
+            out.push_str(
+                "    # With --changed-since, skip the check if none of these files changed\n",
+            );
+            out.push_str(&format!("    paths: [{}]\n", paths.join(", ")));
+        }
+    }
+    out
+}
+
+/// Write a configuration at `path` for the tools detected in the repository. If `probe` is set,
+/// the versions of the installed tools are used as requirements.
+pub fn init(repository: &Path, path: &Path, probe: bool, force: bool) -> anyhow::Result<()> {
+    if !force && path.exists() {
+        return Err(anyhow::anyhow!(
+            "The configuration {:?} already exists. Use --force to overwrite it.",
+            path
+        ))
+        .with_exit_code(ExitCode::Usage);
+    }
+    let mut checks = detect(repository)?;
+    if probe {
+        for check in &mut checks {
+            check.probe(repository);
+        }
+    }
+    std::fs::write(path, render(&checks))
+        .with_context(|| format!("Failed to write configuration at {:?}", path))?;
+    println!(
+        "Wrote {:?} with {} detected checks: {}",
+        path,
+        checks.len(),
+        checks.iter().map(|c| c.name).collect::<Vec<_>>().join(", ")
+    );
+    Ok(())
+}
+
+#[test]
+fn scaffold() {
+    let folder = tempfile::tempdir().unwrap();
+    std::fs::write(
+        folder.path().join("Cargo.toml"),
+        "[workspace]\nmembers = []\n",
+    )
+    .unwrap();
+    std::fs::write(
+        folder.path().join("package.json"),
+        r#"{"devDependencies": {"prettier": "3.3.3"}}"#,
+    )
+    .unwrap();
+    std::fs::write(
+        folder.path().join("rust-toolchain.toml"),
+        "[toolchain]\nchannel = \"1.80.0\"\n",
+    )
+    .unwrap();
+    let mut checks = detect(folder.path()).unwrap();
+    assert_eq!(checks[0].command, "cargo +1.80.0 fmt --all -- --check");
+    checks[2].version = Some(Ok(">=3.3.3".into()));
+    let config = crate::Config::from_source(Path::new("checkalot.yaml"), &render(&checks)).unwrap();
+    assert_eq!(
+        config
+            .checks
+            .iter()
+            .map(crate::CheckEntry::name)
+            .collect::<Vec<_>>(),
+        ["version", "fmt", "clippy", "prettier"]
+    );
+}
+
+#[test]
+fn python_tools() {
+    let folder = tempfile::tempdir().unwrap();
+    let detect = |pyproject: &str| -> Vec<&str> {
+        std::fs::write(folder.path().join("pyproject.toml"), pyproject).unwrap();
+        detect(folder.path())
+            .unwrap()
+            .iter()
+            .map(|c| c.name)
+            .collect()
+    };
+    assert_eq!(
+        detect(
+            r#"[project]
+description = "Faster than ruff"
+dependencies = ["requests>=2"]
+
+[tool.black]
+line-length = 100
+"#
+        ),
+        ["black"]
+    );
+    assert_eq!(
+        detect(
+            r#"[dependency-groups]
+dev = ["Ruff>=0.5; python_version >= '3.9'"]
+"#
+        ),
+        ["ruff", "ruff-format"]
+    );
+    assert_eq!(
+        detect("[tool.poetry.group.dev.dependencies]\nblack = \"^24\"\n"),
+        ["black"]
+    );
+}
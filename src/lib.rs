@@ -2,8 +2,32 @@ pub mod config;
 pub mod mains;
 use config::*;
 pub mod checks;
+pub mod commits;
+pub mod condition;
 pub mod errors;
+mod extends;
+pub mod files;
+pub mod git;
+mod init;
+pub mod license;
+mod local;
+mod matrix;
+pub mod nested;
+pub mod pattern;
+pub mod presets;
+pub mod runner;
+pub mod scan;
+pub mod secrets;
+pub mod select;
+pub mod syntax;
+mod templates;
+pub mod todos;
+pub mod ui;
+pub mod validate;
+pub mod variables;
+pub mod whitespace;
 
+use std::io::Seek;
 use std::path::Path;
 
 use anyhow::Context;
@@ -45,6 +69,30 @@ channel = \"1.75.0\"
     }
 }
 
+/// Cache folder of checkalot, for bundles and remote configurations.
+pub fn cache_dir() -> anyhow::Result<std::path::PathBuf> {
+    Ok(dirs::cache_dir()
+        .context("Failed to find cache dir")?
+        .join("checkalot"))
+}
+
+/// Download to a temporary file, verifying the SHA-256 checksum.
+pub fn download(url: &str, sha256: &str) -> anyhow::Result<std::fs::File> {
+    let mut reader = ureq::get(url).call()?.into_reader();
+    let mut tempfile = tempfile::tempfile()?;
+    std::io::copy(&mut reader, &mut tempfile)?;
+    tempfile.seek(std::io::SeekFrom::Start(0))?;
+    let actual = crate::sha256(&mut tempfile)?;
+    anyhow::ensure!(
+        actual == sha256,
+        "Mismatching checksum: got {}, wanted {}",
+        actual,
+        sha256
+    );
+    tempfile.seek(std::io::SeekFrom::Start(0))?;
+    Ok(tempfile)
+}
+
 pub fn sha256(mut r: impl std::io::Read) -> anyhow::Result<String> {
     let mut hasher = sha2::Sha256::new();
     std::io::copy(&mut r, &mut hasher)?;
@@ -0,0 +1,435 @@
+//! Built-in `license-header` check, requiring a license header at the top of the files.
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use regex::{Captures, Regex};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::errors::{CheckError, Finding};
+use crate::scan::{self, Scope};
+
+/// Line comment prefixes by extension or file name.
+/// Data formats such as YAML and TOML are left out, as their files are often generated or vendored;
+/// add them to `comments` to opt in.
+const DEFAULT_COMMENTS: [(&str, &str); 27] = [
+    ("rs", "//"),
+    ("c", "//"),
+    ("h", "//"),
+    ("cc", "//"),
+    ("cpp", "//"),
+    ("hpp", "//"),
+    ("cs", "//"),
+    ("go", "//"),
+    ("java", "//"),
+    ("kt", "//"),
+    ("scala", "//"),
+    ("swift", "//"),
+    ("js", "//"),
+    ("jsx", "//"),
+    ("ts", "//"),
+    ("tsx", "//"),
+    ("proto", "//"),
+    ("py", "#"),
+    ("sh", "#"),
+    ("bash", "#"),
+    ("rb", "#"),
+    ("pl", "#"),
+    ("Dockerfile", "#"),
+    ("Makefile", "#"),
+    ("sql", "--"),
+    ("lua", "--"),
+    ("hs", "--"),
+];
+
+/// Block comment delimiters by extension, for languages without line comments.
+const DEFAULT_BLOCK_COMMENTS: [(&str, &str, &str); 9] = [
+    ("css", "/*", "*/"),
+    ("html", "<!--", "-->"),
+    ("htm", "<!--", "-->"),
+    ("xml", "<!--", "-->"),
+    ("xhtml", "<!--", "-->"),
+    ("vue", "<!--", "-->"),
+    ("svelte", "<!--", "-->"),
+    ("ml", "(*", "*)"),
+    ("mli", "(*", "*)"),
+];
+
+/// How the header is commented out.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, JsonSchema)]
+#[serde(untagged)]
+pub enum Comment {
+    /// Prefix of each line, e.g. `#`.
+    Line(String),
+    /// Opening and closing delimiters of a block, e.g. `["/*", "*/"]`. One-line headers are
+    /// commented on a single line, other headers between lines with the delimiters.
+    Block(String, String),
+}
+
+impl Comment {
+    /// Comment out the lines of the header, with the delimiters transformed by `delimiter`.
+    fn comment_out(&self, lines: Vec<String>, delimiter: impl Fn(&str) -> String) -> Vec<String> {
+        match self {
+            Comment::Line(prefix) => lines
+                .into_iter()
+                .map(|line| {
+                    if line.is_empty() {
+                        delimiter(prefix)
+                    } else {
+                        format!("{} {}", delimiter(prefix), line)
+                    }
+                })
+                .collect(),
+            Comment::Block(open, close) if lines.len() == 1 => {
+                vec![format!(
+                    "{} {} {}",
+                    delimiter(open),
+                    lines[0],
+                    delimiter(close)
+                )]
+            }
+            Comment::Block(open, close) => std::iter::once(delimiter(open))
+                .chain(lines)
+                .chain(std::iter::once(delimiter(close)))
+                .collect(),
+        }
+    }
+}
+
+/// Lines staying above the header, and the rest of the content: a shebang (`#![` starts a Rust
+/// inner attribute), an XML declaration, and an encoding declaration of Python or Ruby, on the
+/// first or second line (PEP 263).
+fn preamble(content: &str) -> (&str, &str) {
+    let is_encoding = |line: &str| {
+        line.trim_start().starts_with('#') && (line.contains("coding:") || line.contains("coding="))
+    };
+    let mut lines = content.split_inclusive('\n');
+    let len = match (lines.next(), lines.next()) {
+        (Some(first), Some(second)) if is_encoding(second) => first.len() + second.len(),
+        (Some(first), _)
+            if (first.starts_with("#!") && !first.starts_with("#!["))
+                || first.starts_with("<?xml")
+                || is_encoding(first) =>
+        {
+            first.len()
+        }
+        _ => 0,
+    };
+    content.split_at(len)
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct LicenseHeader {
+    /// Files to scan.
+    #[serde(default, skip_serializing_if = "Scope::is_default")]
+    scope: Scope,
+    /// Patterns (in the `.gitignore` syntax) of the files to scan. All files if empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    include: Vec<String>,
+    /// Patterns (in the `.gitignore` syntax) of the files to ignore.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    exclude: Vec<String>,
+    /// Lines of the header, without comment prefix. `{license}`, `{holder}` and `{year}` are
+    /// replaced; any year or range of years is accepted for `{year}`.
+    #[serde(default = "default_header")]
+    header: String,
+    /// SPDX license expression, e.g. `MIT OR Apache-2.0`. Required by the default header.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    license: Option<String>,
+    /// Copyright holder.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    holder: Option<String>,
+    /// Comments by extension or file name, in addition to the built-in ones for common
+    /// languages (not including data formats such as YAML or TOML): a line comment prefix, e.g.
+    /// `{ex: "#"}`, or block comment delimiters, e.g. `{less: ["/*", "*/"]}`. Files without
+    /// comments are skipped.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    comments: BTreeMap<String, Comment>,
+    /// Require `{year}`, or the end of the range of years, to be the current year. The fix
+    /// extends the range.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    current_year: bool,
+}
+
+fn default_header() -> String {
+    "SPDX-License-Identifier: {license}".into()
+}
+
+/// Regular expressions matching the header for a comment: with the configured license and
+/// holder, and with any license and holder, to detect headers of other licenses or holders.
+struct Matcher {
+    comment: Comment,
+    exact: Regex,
+    other: Regex,
+}
+
+impl LicenseHeader {
+    /// Fields used by the header but not set.
+    pub(crate) fn missing_fields(&self) -> impl Iterator<Item = &'static str> + '_ {
+        [("license", &self.license), ("holder", &self.holder)]
+            .into_iter()
+            .filter(|(name, value)| {
+                value.is_none() && self.header.contains(&format!("{{{}}}", name))
+            })
+            .map(|(name, _)| name)
+    }
+    fn placeholder<'a>(
+        &self,
+        name: &str,
+        value: &'a Option<String>,
+    ) -> Result<&'a str, CheckError> {
+        let used = self.header.contains(&format!("{{{}}}", name));
+        match value {
+            Some(value) => Ok(value),
+            None if used => Err(CheckError::InvalidHeader(format!(
+                "the header uses `{{{}}}`, but `{}` is not set",
+                name, name
+            ))),
+            None => Ok(""),
+        }
+    }
+    fn matcher(&self, comment: Comment) -> Result<Matcher, CheckError> {
+        let license = self.placeholder("license", &self.license)?;
+        let holder = self.placeholder("holder", &self.holder)?;
+        let pattern = |license: &str, holder: &str| {
+            let mut year = r"(\d{4})(?:\s*-\s*(\d{4}))?";
+            let lines: Vec<_> = self
+                .header
+                .lines()
+                .map(|line| {
+                    let mut pattern = String::new();
+                    for (i, part) in line.split("{year}").enumerate() {
+                        if i > 0 {
+                            pattern.push_str(year);
+                            // Only the first year is captured.
+                            year = r"\d{4}(?:\s*-\s*\d{4})?";
+                        }
+                        pattern.push_str(
+                            &regex::escape(part)
+                                .replace(r"\{license\}", license)
+                                .replace(r"\{holder\}", holder),
+                        );
+                    }
+                    pattern
+                })
+                .collect();
+            let lines: Vec<_> = comment
+                .comment_out(lines, regex::escape)
+                .into_iter()
+                .map(|line| line + r"[ \t]*")
+                .collect();
+            Regex::new(&format!(r"\A{}(?:\r?\n|\z)", lines.join(r"\r?\n")))
+        };
+        let exact = pattern(&regex::escape(license), &regex::escape(holder))?;
+        let other = pattern(".*?", ".*?")?;
+        Ok(Matcher {
+            comment,
+            exact,
+            other,
+        })
+    }
+    fn render(&self, comment: &Comment, year: &str) -> String {
+        let lines = self
+            .header
+            .lines()
+            .map(|line| {
+                line.replace("{license}", self.license.as_deref().unwrap_or_default())
+                    .replace("{holder}", self.holder.as_deref().unwrap_or_default())
+                    .replace("{year}", year)
+            })
+            .collect();
+        comment
+            .comment_out(lines, str::to_owned)
+            .into_iter()
+            .map(|line| line + "\n")
+            .collect()
+    }
+    /// Problem with the header of the content, and the fixed content. Headers with another
+    /// license or holder are not fixed, as the file might come from a third party.
+    fn check(
+        &self,
+        matcher: &Matcher,
+        content: &str,
+        year: i32,
+    ) -> Option<(String, Option<String>)> {
+        let (first, body) = preamble(content);
+        let current = year.to_string();
+        // Keep the first year of the existing header, extending the range if required.
+        let year_range = |captures: &Captures| {
+            let start = &captures[1];
+            if self.current_year {
+                if start == current {
+                    current.clone()
+                } else {
+                    format!("{}-{}", start, current)
+                }
+            } else {
+                match captures.get(2) {
+                    Some(end) => format!("{}-{}", start, end.as_str()),
+                    None => start.into(),
+                }
+            }
+        };
+        if let Some(captures) = matcher.exact.captures(body) {
+            let end = captures.get(2).or(captures.get(1));
+            if !self.current_year || end.map_or(true, |end| end.as_str() == current) {
+                return None;
+            }
+            let rest = &body[captures[0].len()..];
+            let fixed = format!(
+                "{}{}{}",
+                first,
+                self.render(&matcher.comment, &year_range(&captures)),
+                rest
+            );
+            Some(("outdated year".into(), Some(fixed)))
+        } else if matcher.other.is_match(body) {
+            Some(("license header with another license or holder".into(), None))
+        } else {
+            let separator = if body.starts_with('\n') || body.is_empty() {
+                ""
+            } else {
+                "\n"
+            };
+            let rest = format!("{}{}", separator, body);
+            let fixed = format!(
+                "{}{}{}",
+                first,
+                self.render(&matcher.comment, &current),
+                rest
+            );
+            Some(("missing license header".into(), Some(fixed)))
+        }
+    }
+    pub(crate) fn execute(&self, repository: &Path, fix: bool) -> Result<(), CheckError> {
+        let matchers: BTreeMap<&str, Matcher> = DEFAULT_COMMENTS
+            .into_iter()
+            .map(|(key, prefix)| (key, Comment::Line(prefix.into())))
+            .chain(
+                DEFAULT_BLOCK_COMMENTS
+                    .into_iter()
+                    .map(|(key, open, close)| (key, Comment::Block(open.into(), close.into()))),
+            )
+            .chain(self.comments.iter().map(|(k, v)| (k.as_str(), v.clone())))
+            .map(|(key, comment)| Ok((key, self.matcher(comment)?)))
+            .collect::<Result<_, CheckError>>()?;
+        let year = time::OffsetDateTime::now_utc().year();
+        let files = scan::list(repository, self.scope, &self.include, &self.exclude)?;
+        let findings = scan::scan(repository, &files, |file, content| {
+            let key = file.path.extension().or(file.path.file_name());
+            let Some(matcher) = key.and_then(|k| matchers.get(k.to_str()?)) else {
+                return vec![];
+            };
+            let Ok(content) = std::str::from_utf8(content) else {
+                return vec![];
+            };
+            if content.is_empty() {
+                return vec![];
+            }
+            let Some((problem, fixed)) = self.check(matcher, content, year) else {
+                return vec![];
+            };
+            let line = preamble(content).0.lines().count() + 1;
+            if fix && fixed.is_some() {
+                // The fix applies to the working tree, even when scanning staged files.
+                let path = repository.join(&file.path);
+                let result = std::fs::read_to_string(&path).and_then(|content| {
+                    match self.check(matcher, &content, year) {
+                        Some((_, Some(fixed))) => std::fs::write(&path, fixed),
+                        _ => Ok(()),
+                    }
+                });
+                return match result {
+                    Ok(()) => vec![],
+                    Err(e) => vec![file.finding(None, format!("failed to fix: {}", e))],
+                };
+            }
+            vec![Finding {
+                location: file.path.display().to_string(),
+                line: Some(line),
+                column: None,
+                message: problem,
+            }]
+        })?;
+        if !findings.is_empty() {
+            return Err(CheckError::Findings(findings));
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn license_headers() {
+    let check: LicenseHeader = serde_yaml::from_str(
+        "header: |-
+  SPDX-License-Identifier: {license}
+  Copyright {year} {holder}
+license: MIT
+holder: Acme
+current_year: true",
+    )
+    .unwrap();
+    let matcher = check.matcher(Comment::Line("#".into())).unwrap();
+    let header = "# SPDX-License-Identifier: MIT\n# Copyright 2020-2024 Acme\n";
+    assert_eq!(check.check(&matcher, header, 2024), None);
+    assert_eq!(
+        check.check(&matcher, header, 2025),
+        Some((
+            "outdated year".into(),
+            Some("# SPDX-License-Identifier: MIT\n# Copyright 2020-2025 Acme\n".into())
+        ))
+    );
+    assert_eq!(
+        check.check(
+            &matcher,
+            "#!/bin/sh\n# SPDX-License-Identifier: GPL\n# Copyright 2023 Other\necho\n",
+            2024
+        ),
+        Some(("license header with another license or holder".into(), None))
+    );
+    assert_eq!(
+        check.check(&matcher, "#!/bin/sh\necho\n", 2024),
+        Some((
+            "missing license header".into(),
+            Some(
+                "#!/bin/sh\n# SPDX-License-Identifier: MIT\n# Copyright 2024 Acme\n\necho\n".into()
+            )
+        ))
+    );
+    assert_eq!(
+        check.check(&matcher, "# -*- coding: latin-1 -*-\nimport os\n", 2024),
+        Some((
+            "missing license header".into(),
+            Some(
+                "# -*- coding: latin-1 -*-\n# SPDX-License-Identifier: MIT\n# Copyright 2024 Acme\n\nimport os\n".into()
+            )
+        ))
+    );
+    let header = "#!/usr/bin/env python\n# coding=utf-8\n# SPDX-License-Identifier: MIT\n# Copyright 2024 Acme\n";
+    assert_eq!(check.check(&matcher, header, 2024), None);
+
+    let block = check
+        .matcher(Comment::Block("<!--".into(), "-->".into()))
+        .unwrap();
+    let header = "<?xml version=\"1.0\"?>\n<!--\nSPDX-License-Identifier: MIT\nCopyright 2024 Acme\n-->\n<a/>\n";
+    assert_eq!(check.check(&block, header, 2024), None);
+    assert_eq!(
+        check.check(&block, "<a/>\n", 2024),
+        Some((
+            "missing license header".into(),
+            Some("<!--\nSPDX-License-Identifier: MIT\nCopyright 2024 Acme\n-->\n\n<a/>\n".into())
+        ))
+    );
+    let check: LicenseHeader = serde_yaml::from_str("license: MIT").unwrap();
+    let block = check
+        .matcher(Comment::Block("/*".into(), "*/".into()))
+        .unwrap();
+    assert_eq!(
+        check.check(&block, "a {}\n", 2024),
+        Some((
+            "missing license header".into(),
+            Some("/* SPDX-License-Identifier: MIT */\n\na {}\n".into())
+        ))
+    );
+}
@@ -0,0 +1,215 @@
+//! Personal overrides of the configuration, which are not committed: `checkalot.local.yaml` next
+//! to the configuration, and `local.yaml` in the checkalot folder of the user configuration
+//! directory, applied in this order on top of the configuration.
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use serde::Deserialize;
+
+use crate::validate::{suggest, Source};
+use crate::{Check, CheckEntry, Config};
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct LocalConfig {
+    /// Names of checks to disable.
+    #[serde(default)]
+    disable: Vec<String>,
+    /// Additional checks, replacing the ones with the same name.
+    #[serde(default)]
+    checks: Vec<CheckEntry>,
+    /// Settings of existing command checks, by name.
+    #[serde(default)]
+    overrides: BTreeMap<String, Overrides>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct Overrides {
+    /// Duration in seconds after which the commands are killed, replacing the one of the check.
+    timeout: Option<u64>,
+    /// Merged with the variables of the check.
+    #[serde(default)]
+    env: BTreeMap<String, String>,
+}
+
+/// `<name>.local.yaml` for `<name>.yaml`.
+fn local_path(path: &Path) -> PathBuf {
+    path.with_extension("local.yaml")
+}
+
+fn user_path() -> Option<PathBuf> {
+    Some(dirs::config_dir()?.join("checkalot").join("local.yaml"))
+}
+
+impl LocalConfig {
+    fn read(path: &Path) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to open local configuration at {:?}", path))?;
+        let source = Source::new(path, &text);
+        Ok(serde_yaml::from_str(&text).map_err(|e| source.error(vec![source.yaml_error(&e)]))?)
+    }
+    /// Apply on top of the configuration. If `strict` is false, unknown check names are ignored,
+    /// as the user file applies to all repositories.
+    fn apply(self, config: &mut Config, path: &Path, strict: bool) -> anyhow::Result<()> {
+        let origin = path.display().to_string();
+        let find = |checks: &[CheckEntry], name: &str| -> anyhow::Result<Option<usize>> {
+            let index = checks.iter().position(|c| c.name() == name);
+            if index.is_none() && strict {
+                let help = suggest(name, checks.iter().map(CheckEntry::name))
+                    .map(|s| format!(" ({})", s))
+                    .unwrap_or_default();
+                anyhow::bail!("Unknown check `{}`{}", name, help);
+            }
+            Ok(index)
+        };
+
+        let checks = config.apply_templates(self.checks)?;
+        let mut checks = crate::matrix::expand(crate::presets::expand(checks));
+        for check in &mut checks {
+            check.origin.clone_from(&origin);
+        }
+        crate::validate::unique_names(&checks)?;
+        crate::variables::validate(&checks)?;
+        crate::extends::merge(&mut config.checks, checks);
+
+        for (name, overrides) in self.overrides {
+            let Some(index) = find(&config.checks, &name)? else {
+                continue;
+            };
+            let check = &mut config.checks[index];
+            let Check::Command { env, timeout, .. } = &mut check.check else {
+                anyhow::bail!("Only command checks can be overridden, not `{}`", name);
+            };
+            if overrides.timeout.is_some() {
+                *timeout = overrides.timeout;
+                check.overridden.push(("timeout".into(), origin.clone()));
+            }
+            if !overrides.env.is_empty() {
+                env.extend(overrides.env);
+                check.overridden.push(("env".into(), origin.clone()));
+            }
+        }
+
+        for name in self.disable {
+            if let Some(index) = find(&config.checks, &name)? {
+                config.checks.remove(index);
+                config.disabled.push((name, origin.clone()));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Config {
+    /// Apply the personal overrides of the configuration at `path`.
+    pub fn apply_local(&mut self, path: &Path) -> anyhow::Result<()> {
+        for (path, strict) in [(user_path(), false), (Some(local_path(path)), true)] {
+            let Some(path) = path.filter(|p| p.exists()) else {
+                continue;
+            };
+            LocalConfig::read(&path)
+                .and_then(|local| local.apply(self, &path, strict))
+                .with_context(|| format!("Failed to apply local configuration {:?}", path))?;
+        }
+        Ok(())
+    }
+    /// Describe the file where each check and setting comes from.
+    pub fn explain(&self) -> String {
+        let mut lines = vec!["Checks:".to_string()];
+        for check in &self.checks {
+            lines.push(format!("  {:<24} {}", check.name(), check.origin));
+            for (setting, origin) in &check.overridden {
+                lines.push(format!("    {:<22} {}", setting, origin));
+            }
+        }
+        if !self.disabled.is_empty() {
+            lines.push("Disabled:".into());
+            for (name, origin) in &self.disabled {
+                lines.push(format!("  {:<24} {}", name, origin));
+            }
+        }
+        lines
+            .iter()
+            .map(|l| l.trim_end().to_string() + "\n")
+            .collect()
+    }
+}
+
+#[test]
+fn apply_overrides() {
+    let mut config = Config::from_source(
+        Path::new("checkalot.yaml"),
+        "checks:
+  - type: command
+    name: clippy
+    command: cargo clippy
+  - type: command
+    name: deny
+    command: cargo deny check",
+    )
+    .unwrap();
+    let local: LocalConfig = serde_yaml::from_str(
+        "disable: [deny]
+overrides:
+  clippy:
+    timeout: 600
+    env: {CARGO_TARGET_DIR: /tmp/target}
+checks:
+  - type: command
+    name: typos
+    command: typos",
+    )
+    .unwrap();
+    local
+        .apply(&mut config, Path::new("checkalot.local.yaml"), true)
+        .unwrap();
+    assert_eq!(
+        config.explain(),
+        "Checks:
+  clippy
+    timeout                checkalot.local.yaml
+    env                    checkalot.local.yaml
+  typos                    checkalot.local.yaml
+Disabled:
+  deny                     checkalot.local.yaml
+"
+    );
+    let local: LocalConfig = serde_yaml::from_str("disable: [clipy]").unwrap();
+    assert!(local
+        .apply(&mut config, Path::new("checkalot.local.yaml"), true)
+        .is_err());
+}
+
+#[test]
+fn override_nested_checks() {
+    let repository = tempfile::tempdir().unwrap();
+    let write = |path: &str, text: &str| {
+        let path = repository.path().join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, text).unwrap();
+    };
+    let clippy = "checks:
+  - type: command
+    name: clippy
+    command: cargo clippy";
+    write("checkalot.yaml", clippy);
+    write("server/checkalot.yaml", clippy);
+    write("checkalot.local.yaml", "disable: [server/clippy]");
+    let config = Config::load(
+        &repository.path().join("checkalot.yaml"),
+        Some(repository.path()),
+        &mut crate::ui::Console::new(crate::ui::Style::detect(true)),
+    )
+    .unwrap();
+    assert_eq!(
+        config
+            .checks
+            .iter()
+            .map(CheckEntry::name)
+            .collect::<Vec<_>>(),
+        ["clippy"]
+    );
+    assert_eq!(config.disabled[0].0, "server/clippy");
+}
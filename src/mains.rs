@@ -1,11 +1,12 @@
-use std::collections::HashSet;
-use std::io::Write;
 use std::path::PathBuf;
 
 use anyhow::Context;
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use colored::Colorize;
 
+use crate::errors::WithExitCode;
+use crate::runner::{self, run_checks};
+use crate::ui::{Console, Reporter, Style};
 use crate::*;
 
 fn find_repository() -> anyhow::Result<PathBuf> {
@@ -21,103 +22,275 @@ fn find_repository() -> anyhow::Result<PathBuf> {
     }
 }
 
+/// Root of the cargo workspace containing the current directory.
+fn find_workspace() -> anyhow::Result<PathBuf> {
+    // Set by cargo when running subcommands.
+    let cargo = std::env::var_os("CARGO").unwrap_or("cargo".into());
+    let output = std::process::Command::new(cargo)
+        .args(["locate-project", "--workspace", "--message-format", "plain"])
+        .output()
+        .context("Failed to run cargo locate-project")?;
+    anyhow::ensure!(
+        output.status.success(),
+        "Failed to locate the cargo workspace: {}",
+        String::from_utf8_lossy(&output.stderr).trim()
+    );
+    let manifest = PathBuf::from(String::from_utf8(output.stdout)?.trim());
+    Ok(manifest
+        .parent()
+        .context("Invalid manifest path from cargo")?
+        .into())
+}
+
 #[derive(Parser)]
 #[clap(version)]
 pub struct Flags {
+    #[command(subcommand)]
+    command: Option<Command>,
     /// Repository root. If not provided, deduced from the current directory.
     repository: Option<PathBuf>,
     /// Skip these checks
     #[clap(long, value_delimiter = ',')]
     skip: Vec<String>,
     /// Only perform these checks
-    #[clap(long, value_delimiter = ',', conflicts_with = "skip")]
+    #[clap(long, value_delimiter = ',')]
     only: Vec<String>,
+    /// Only perform the checks matching this expression, e.g. `tag:rust and not name:clippy*`
+    #[clap(long)]
+    select: Option<String>,
+    /// Use the check selection and options of a profile from the configuration
+    #[clap(long)]
+    profile: Option<String>,
+    /// Skip checks whose `paths` do not match files changed since this git revision
+    #[clap(long)]
+    changed_since: Option<String>,
+    /// Run all checks even if some fail
+    #[clap(long)]
+    keep_going: bool,
+    /// Write a JSON report of the results at this location
+    #[clap(long)]
+    report: Option<PathBuf>,
     /// Configuration path relative to repository root
-    #[clap(long, default_value = "checkalot.yaml")]
+    #[clap(long, global = true, default_value = "checkalot.yaml")]
     config: PathBuf,
+    /// Also run the checks of configurations with the same file name in sub-folders, e.g. in a
+    /// monorepo. Files ignored by git are skipped.
+    #[clap(long, global = true)]
+    recursive: bool,
     /// Tries to fix errors
     #[clap(long)]
     fix: bool,
+    /// ASCII output without emojis nor spinners. Colors can be disabled with NO_COLOR.
+    #[clap(long, global = true)]
+    plain: bool,
+    /// Deduce the repository from the cargo workspace rather than from git.
+    #[clap(skip)]
+    cargo: bool,
 }
 
+#[derive(Subcommand)]
+enum Command {
+    /// Print the JSON schema of the configuration file
+    Schema,
+    /// Create a configuration file with checks for the tools detected in the repository
+    Init {
+        /// Require the versions of the installed tools
+        #[clap(long)]
+        probe: bool,
+        /// Overwrite an existing configuration
+        #[clap(long)]
+        force: bool,
+    },
+    /// Validate the configuration file, without running checks
+    Validate,
+    /// Print the configuration
+    Config {
+        /// Include the checks from `extends`
+        #[clap(long)]
+        resolved: bool,
+        /// List the checks with the file where they and their settings come from, including the
+        /// local overrides
+        #[clap(long, conflicts_with = "resolved")]
+        explain: bool,
+    },
+}
+
+/// Selection of checks from the command line or a profile.
+fn selection<'a>(
+    profile: Option<&'a str>,
+    only: &'a [String],
+    skip: &'a [String],
+    select: &[String],
+) -> anyhow::Result<Selection<'a>> {
+    let select = select
+        .iter()
+        .map(|s| {
+            s.parse::<select::Selector>()
+                .with_context(|| format!("Invalid selection expression `{}`", s))
+                .with_exit_code(errors::ExitCode::Usage)
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    Ok(Selection {
+        profile,
+        only: only.iter().map(String::as_str).collect(),
+        skip: skip.iter().map(String::as_str).collect(),
+        select,
+    })
+}
+
+/// Exits with a code describing the failure category, see [`errors::ExitCode`].
 pub fn main(args: Flags) -> anyhow::Result<()> {
     if let Err(e) = main_impl(args) {
         println!("{}: {:?}", "Error".red(), e);
-        std::process::exit(1);
+        std::process::exit(errors::exit_code(&e) as i32);
     }
     Ok(())
 }
-/// Returns `true` if at least one fix ran
-fn run_checks(config: &Config, repository: &Path, fix: bool) -> anyhow::Result<bool> {
-    let mut stdout = std::io::stdout();
-    let n_checks = config.checks.len();
-    let start = std::time::Instant::now();
-
-    println!("Executing {} checks in {:?}", n_checks, repository);
-
-    let mut ran_fix = false;
-
-    for (i, check) in config.checks.iter().enumerate() {
-        let start_check = std::time::Instant::now();
-
-        let header = format!("[{:>2}/{}] ", i + 1, n_checks);
-        print!("{}Executing {:<20} ", header, check.name());
-        stdout.flush()?;
-
-        match check.execute(repository, false) {
-            Err(_) if fix => {
-                print!("🟠 ");
-                stdout.flush()?;
-                if let Err(e) = check.execute(repository, true) {
-                    e.print();
-                    anyhow::bail!("Fixing {} failed", check.name());
-                }
-                ran_fix = true;
-
-                println!("{:.2} s", start_check.elapsed().as_secs_f32());
-            }
-            Err(e) => {
-                println!("❌ {:.2} s", start_check.elapsed().as_secs_f32());
-                e.print();
-                anyhow::bail!(
-                    "The check '{}' has failed. Try running with --fix.",
-                    check.name()
-                );
-            }
-            Ok(_) => {
-                println!("✅ {:.2} s", start_check.elapsed().as_secs_f32());
-            }
-        }
-    }
-    if !ran_fix {
-        println!(
-            "✅ All {} checks passed in {:.2} s",
-            n_checks,
-            start.elapsed().as_secs_f32()
-        );
-    }
-    Ok(ran_fix)
+/// Entrypoint of `cargo checkalot`.
+pub fn cargo_main(mut args: Flags) -> anyhow::Result<()> {
+    args.cargo = true;
+    main(args)
 }
 fn main_impl(args: Flags) -> anyhow::Result<()> {
-    println!("{} {}", "checkalot".blue(), env!("CARGO_PKG_VERSION"));
+    if let Some(Command::Schema) = args.command {
+        print!("{}", Config::schema());
+        return Ok(());
+    }
+
+    let style = Style::detect(args.plain);
+    style.apply();
+    let mut console = Console::new(style);
 
     let repository = if let Some(repository) = args.repository.clone() {
         repository
+    } else if args.cargo {
+        find_workspace().with_exit_code(errors::ExitCode::Environment)?
     } else {
         find_repository()?
     };
 
-    let mut config = Config::load(&repository.join(&args.config))?;
+    let config_path = repository.join(&args.config);
+    let config_name = args
+        .config
+        .file_name()
+        .context("Invalid configuration path")
+        .with_exit_code(errors::ExitCode::Usage)?;
+    // In recursive mode, the root configuration is optional.
+    let root_optional =
+        args.recursive && !Config::exists(&config_path).with_exit_code(errors::ExitCode::Config)?;
+    match args.command {
+        Some(Command::Init { probe, force }) => {
+            return init::init(&repository, &config_path, probe, force);
+        }
+        Some(Command::Validate) => {
+            if !root_optional {
+                Config::read(&config_path, &mut console)
+                    .with_exit_code(errors::ExitCode::Config)?;
+                println!("The configuration {:?} is valid", config_path);
+            }
+            if args.recursive {
+                for folder in nested::discover(&repository, config_name.as_ref())? {
+                    let path = repository.join(folder).join(config_name);
+                    let nested = Config::read(&path, &mut console)
+                        .with_exit_code(errors::ExitCode::Config)?;
+                    println!("The configuration {:?} is valid", path);
+                    if let Some(warning) = nested.ignored_profiles(&path) {
+                        println!("{} {}", "Warning:".yellow(), warning);
+                    }
+                }
+            }
+            return Ok(());
+        }
+        Some(Command::Config { explain: true, .. }) => {
+            let mut console = Console::new(style).info_on_stderr();
+            let mut config = if root_optional {
+                Config::default()
+            } else {
+                Config::read(&config_path, &mut console).with_exit_code(errors::ExitCode::Config)?
+            };
+            if args.recursive {
+                config.include_nested(&repository, &config_path, &mut console)?;
+            }
+            config
+                .apply_local(&config_path)
+                .with_exit_code(errors::ExitCode::Config)?;
+            print!("{}", config.explain());
+            return Ok(());
+        }
+        Some(Command::Config { resolved, .. }) => {
+            let mut console = Console::new(style).info_on_stderr();
+            let config = if resolved {
+                Config::read(&config_path, &mut console)
+            } else {
+                Config::parse_root(&config_path)
+            }
+            .with_exit_code(errors::ExitCode::Config)?;
+            print!("{}", serde_yaml::to_string(&config)?);
+            return Ok(());
+        }
+        _ => {}
+    }
+
+    println!("{} {}", "checkalot".blue(), env!("CARGO_PKG_VERSION"));
+
+    let mut config = Config::load(
+        &config_path,
+        args.recursive.then_some(&repository),
+        &mut console,
+    )?;
 
-    let skip: HashSet<_> = args.skip.iter().map(String::as_str).collect();
-    let only: HashSet<_> = args.only.iter().map(String::as_str).collect();
-    config.filter(only, skip)?;
+    let mut changed_since = args.changed_since;
+    let mut keep_going = args.keep_going;
+    let mut report_path = args.report;
+    let profile = args
+        .profile
+        .as_deref()
+        .map(|name| config.take_profile(name))
+        .transpose()?;
+    let mut selections = vec![];
+    if let (Some(name), Some(profile)) = (&args.profile, &profile) {
+        println!("{} {}", "Using profile".yellow(), name);
+        // The selection of the command line further narrows the one of the profile.
+        selections.push(selection(
+            Some(name),
+            &profile.only,
+            &profile.skip,
+            profile.select.as_slice(),
+        )?);
+    }
+    selections.push(selection(
+        None,
+        &args.only,
+        &args.skip,
+        args.select.as_slice(),
+    )?);
+    config.filter(&selections)?;
+    if let Some(profile) = profile {
+        changed_since = changed_since.or(profile.changed_since);
+        keep_going |= profile.keep_going;
+        report_path = report_path.or(profile.report);
+    }
 
-    let ran_fix = run_checks(&config, &repository, args.fix)?;
+    let changed_files = changed_since
+        .map(|since| git::changed_files(&repository, &since))
+        .transpose()
+        .context("Failed to list changed files")?;
 
-    if args.fix && ran_fix {
-        println!("\nRunning all checks again to ensure that fixes were successful.\n",);
-        run_checks(&config, &repository, false)?;
+    let mut options = runner::Options {
+        fix: args.fix,
+        keep_going,
+        changed_files,
+    };
+    let mut report = run_checks(&config, &repository, &options, &mut console)?;
+
+    if args.fix && report.ran_fix() {
+        console.info("\nRunning all checks again to ensure that fixes were successful.\n");
+        options.fix = false;
+        report = run_checks(&config, &repository, &options, &mut console)?;
+    }
+    if let Some(path) = report_path {
+        report.write(&repository.join(path))?;
     }
+    report.into_result()?;
     Ok(())
 }
@@ -0,0 +1,190 @@
+//! Expansion of checks with a `matrix` into concrete checks.
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use crate::{Check, CheckEntry, CommandSpec};
+
+/// Expand the checks with a `matrix`, keeping the order.
+pub fn expand(checks: Vec<CheckEntry>) -> Vec<CheckEntry> {
+    checks.into_iter().flat_map(CheckEntry::expand).collect()
+}
+
+/// All combinations of the values of the axes.
+fn combinations(matrix: &BTreeMap<String, Vec<String>>) -> Vec<Vec<(&str, &str)>> {
+    matrix
+        .iter()
+        .fold(vec![vec![]], |combinations, (axis, values)| {
+            combinations
+                .iter()
+                .flat_map(|c| {
+                    values.iter().map(move |v| {
+                        let mut c = c.clone();
+                        c.push((axis.as_str(), v.as_str()));
+                        c
+                    })
+                })
+                .collect()
+        })
+}
+
+/// Replace `{axis}` placeholders.
+pub(crate) fn substitute(template: &str, values: &[(&str, &str)]) -> String {
+    values.iter().fold(template.into(), |s, (axis, value)| {
+        s.replace(&format!("{{{}}}", axis), value)
+    })
+}
+
+impl CommandSpec {
+    pub(crate) fn map(&self, f: impl FnOnce(&str) -> String) -> Self {
+        match self {
+            CommandSpec::Simple(command) => CommandSpec::Simple(f(command)),
+            CommandSpec::Complex {
+                command,
+                success_statuses,
+            } => CommandSpec::Complex {
+                command: f(command),
+                success_statuses: success_statuses.clone(),
+            },
+        }
+    }
+    pub(crate) fn substitute(&self, values: &[(&str, &str)]) -> Self {
+        self.map(|command| substitute(command, values))
+    }
+}
+
+impl CheckEntry {
+    fn expand(self) -> Vec<CheckEntry> {
+        let Check::Command { matrix, .. } = &self.check else {
+            return vec![self];
+        };
+        if matrix.is_empty() {
+            return vec![self];
+        }
+        combinations(matrix)
+            .into_iter()
+            .map(|values| {
+                let mut entry = self.clone();
+                // All the fields are listed, so that new fields are not forgotten.
+                if let Check::Command {
+                    name,
+                    command,
+                    uses,
+                    with,
+                    fix_command,
+                    folder,
+                    version_command,
+                    version: _,
+                    paths,
+                    matrix,
+                    output,
+                    env,
+                    timeout: _,
+                } = &mut entry.check
+                {
+                    let text = |s: &mut String| *s = substitute(s, &values);
+                    let path = |p: &mut PathBuf| {
+                        *p = substitute(&p.to_string_lossy(), &values).into();
+                    };
+                    // In commands, the values are quoted like variables, to stay single arguments.
+                    let spec = |c: &mut CommandSpec| {
+                        *c = c.map(|c| crate::variables::substitute_command(c, &values));
+                    };
+                    matrix.clear();
+                    text(name);
+                    uses.iter_mut().for_each(text);
+                    with.values_mut().for_each(text);
+                    paths.iter_mut().for_each(text);
+                    env.values_mut().for_each(text);
+                    folder.iter_mut().for_each(path);
+                    output.iter_mut().for_each(path);
+                    command
+                        .iter_mut()
+                        .chain(fix_command)
+                        .chain(version_command)
+                        .for_each(spec);
+                }
+                entry
+            })
+            .collect()
+    }
+}
+
+#[test]
+fn expand_matrix() {
+    let checks: Vec<CheckEntry> = serde_yaml::from_str(
+        r#"
+- type: command
+  name: clippy-{folder}-{features}
+  command: cargo clippy --features "{features}"
+  folder: "{folder}"
+  matrix:
+    folder: [server, client]
+    features: [default, full]
+- type: git-is-clean
+"#,
+    )
+    .unwrap();
+    let checks = expand(checks);
+    assert_eq!(
+        checks.iter().map(CheckEntry::name).collect::<Vec<_>>(),
+        [
+            "clippy-server-default",
+            "clippy-client-default",
+            "clippy-server-full",
+            "clippy-client-full",
+            "git-is-clean"
+        ]
+    );
+    let Check::Command {
+        command, folder, ..
+    } = &checks[3].check
+    else {
+        panic!()
+    };
+    assert_eq!(
+        command.as_ref().unwrap().command(),
+        r#"cargo clippy --features "full""#
+    );
+    assert_eq!(folder.as_ref().unwrap().to_str().unwrap(), "client");
+}
+
+#[test]
+fn expand_all_fields() {
+    let checks: Vec<CheckEntry> = serde_yaml::from_str(
+        r#"
+- type: command
+  name: clippy-{toolchain}
+  command: cargo +{toolchain} clippy --features {features} -- "{features}"
+  version_command: cargo +{toolchain} clippy --version
+  version: ">=0.1"
+  env: {RUSTUP_TOOLCHAIN: "{toolchain}"}
+  paths: ["{toolchain}/**"]
+  matrix:
+    toolchain: [nightly]
+    features: [a b]
+"#,
+    )
+    .unwrap();
+    let checks = expand(checks);
+    crate::variables::validate(&checks).unwrap();
+    let Check::Command {
+        command,
+        version_command,
+        env,
+        paths,
+        ..
+    } = &checks[0].check
+    else {
+        panic!()
+    };
+    assert_eq!(
+        command.as_ref().unwrap().command(),
+        r#"cargo +nightly clippy --features 'a b' -- "a b""#
+    );
+    assert_eq!(
+        version_command.as_ref().unwrap().command(),
+        "cargo +nightly clippy --version"
+    );
+    assert_eq!(env["RUSTUP_TOOLCHAIN"], "nightly");
+    assert_eq!(paths, &["nightly/**"]);
+}
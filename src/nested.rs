@@ -0,0 +1,145 @@
+//! Configurations of sub-projects in monorepos, discovered with `--recursive`.
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use colored::Colorize;
+
+use crate::errors::{ExitCode, WithExitCode};
+use crate::ui::Reporter;
+use crate::{CheckEntry, Config};
+
+/// Folders, relative to the repository root, containing a configuration file named `filename`.
+/// Files ignored by git are skipped, as well as the root configuration.
+pub fn discover(repository: &Path, filename: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let mut folders = vec![];
+    for entry in ignore::WalkBuilder::new(repository)
+        .sort_by_file_path(Path::cmp)
+        .build()
+    {
+        let entry = entry?;
+        if entry.file_type().is_some_and(|t| t.is_file())
+            && entry.file_name() == filename.as_os_str()
+        {
+            let folder = entry
+                .path()
+                .parent()
+                .and_then(|p| p.strip_prefix(repository).ok())
+                .unwrap_or(Path::new(""));
+            if !folder.as_os_str().is_empty() {
+                folders.push(folder.into());
+            }
+        }
+    }
+    Ok(folders)
+}
+
+impl Config {
+    /// Add the checks of the configurations nested in the repository, with the same file name as
+    /// the root configuration `path`. Their checks run in their folder and are prefixed with it,
+    /// e.g. `crates/server/clippy`.
+    pub fn include_nested(
+        &mut self,
+        repository: &Path,
+        path: &Path,
+        reporter: &mut dyn Reporter,
+    ) -> anyhow::Result<()> {
+        let filename = Path::new(
+            path.file_name()
+                .context("Invalid configuration path")
+                .with_exit_code(ExitCode::Usage)?,
+        );
+        for folder in discover(repository, filename).context("Failed to find configurations")? {
+            let path = repository.join(&folder).join(filename);
+            let nested = Config::read(&path, reporter).with_exit_code(ExitCode::Config)?;
+            if let Some(warning) = nested.ignored_profiles(&path) {
+                println!("{} {}", "Warning:".yellow(), warning);
+            }
+            if nested.bundle.is_some() {
+                return Err(anyhow::anyhow!(
+                    "Bundles are only supported in the root configuration ({:?})",
+                    path
+                ))
+                .with_exit_code(ExitCode::Config);
+            }
+            self.checks
+                .extend(nested.checks.into_iter().map(|c| c.nest(&folder)));
+        }
+        Ok(())
+    }
+}
+
+impl Config {
+    /// Describe the profiles of a nested configuration at `path`, which do not apply with
+    /// `--recursive`.
+    pub fn ignored_profiles(&self, path: &Path) -> Option<String> {
+        if self.profiles.is_empty() {
+            return None;
+        }
+        let names: Vec<_> = self.profiles.keys().map(|p| format!("`{}`", p)).collect();
+        Some(format!(
+            "The profiles {} of {:?} are ignored, as only the profiles of the root configuration apply to nested checks",
+            names.join(", "),
+            path
+        ))
+    }
+}
+
+impl CheckEntry {
+    fn nest(mut self, folder: &Path) -> Self {
+        let prefix = folder
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        self.qualified_name = Some(format!("{}/{}", prefix, self.name()));
+        self.folder = folder.join(&self.folder);
+        self
+    }
+    /// Whether the check concerns one of the files, given relative to the repository root.
+    /// Checks of nested configurations without `paths` concern all files of their folder.
+    pub(crate) fn matches_changes(&self, changed_files: &[PathBuf]) -> anyhow::Result<bool> {
+        let mut files = changed_files
+            .iter()
+            .filter_map(|f| f.strip_prefix(&self.folder).ok());
+        if self.paths().is_empty() {
+            return Ok(self.folder.as_os_str().is_empty() || files.count() > 0);
+        }
+        let mut globs = globset::GlobSetBuilder::new();
+        for path in self.paths() {
+            globs.add(globset::Glob::new(path).with_exit_code(ExitCode::Config)?);
+        }
+        let globs = globs.build()?;
+        Ok(files.any(|f| globs.is_match(f)))
+    }
+}
+
+#[test]
+fn nested_changes() {
+    let checks: Vec<CheckEntry> = serde_yaml::from_str(
+        "
+- type: command
+  name: clippy
+  command: cargo clippy
+- type: command
+  name: prettier
+  command: prettier --check .
+  paths: ['*.md']
+",
+    )
+    .unwrap();
+    let checks: Vec<_> = checks
+        .into_iter()
+        .map(|c| c.nest(Path::new("crates/server")))
+        .collect();
+    assert_eq!(checks[0].name(), "crates/server/clippy");
+    let changed = |files: &[&str]| -> Vec<bool> {
+        let files: Vec<PathBuf> = files.iter().map(PathBuf::from).collect();
+        checks
+            .iter()
+            .map(|c| c.matches_changes(&files).unwrap())
+            .collect()
+    };
+    assert_eq!(changed(&["crates/client/README.md"]), [false, false]);
+    assert_eq!(changed(&["crates/server/src/main.rs"]), [true, false]);
+    assert_eq!(changed(&["crates/server/README.md"]), [true, true]);
+}
@@ -0,0 +1,211 @@
+//! Built-in `pattern` check, with rules forbidding or requiring regular expressions in files.
+use std::path::Path;
+
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::errors::{CheckError, Finding};
+use crate::scan::{self, LineIndex, Patterns, Scope};
+
+/// Comment allowing matches of rules, e.g. `// checkalot: allow(no-dbg, no-unwrap)`.
+const ALLOW: &str = r"checkalot:\s*allow\(([^)]*)\)";
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Pattern {
+    /// Files to scan.
+    #[serde(default, skip_serializing_if = "Scope::is_default")]
+    scope: Scope,
+    /// Patterns (in the `.gitignore` syntax) of the files to scan. All files if empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    include: Vec<String>,
+    /// Patterns (in the `.gitignore` syntax) of the files to ignore.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    exclude: Vec<String>,
+    rules: Vec<Rule>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Rule {
+    /// Name, used in the diagnostics and in allow comments.
+    name: String,
+    /// Regular expression, e.g. `dbg!\(`.
+    regex: String,
+    /// Explanation shown with the diagnostics.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message: Option<String>,
+    /// Patterns (in the `.gitignore` syntax) of the files the rule applies to. All scanned files
+    /// if empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    paths: Vec<String>,
+    /// Patterns (in the `.gitignore` syntax) of the files the rule does not apply to.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    exclude: Vec<String>,
+    /// Require each file to contain the regular expression, rather than forbidding it.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    required: bool,
+    /// Allow matches on lines with a `checkalot: allow(<name>)` comment, or following a line
+    /// with only this comment.
+    #[serde(
+        default = "crate::config::default_true",
+        skip_serializing_if = "crate::config::is_true"
+    )]
+    allow_comment: bool,
+}
+
+struct CompiledRule<'a> {
+    rule: &'a Rule,
+    regex: Regex,
+    paths: Patterns,
+    exclude: Patterns,
+}
+impl CompiledRule<'_> {
+    fn applies(&self, path: &Path) -> bool {
+        (self.paths.is_empty() || self.paths.is_match(path)) && !self.exclude.is_match(path)
+    }
+    fn message(&self, default: &str) -> String {
+        format!(
+            "[{}] {}",
+            self.rule.name,
+            self.rule.message.as_deref().unwrap_or(default)
+        )
+    }
+}
+
+/// Whether the line contains a `checkalot: allow(...)` comment for the rule, and whether it
+/// contains nothing else.
+fn allow_comment(allow: &Regex, line: &str, name: &str) -> Option<bool> {
+    let captures = allow.captures(line)?;
+    captures[1].split(',').any(|n| n.trim() == name).then(|| {
+        let start = captures.get(0).unwrap().start();
+        line[..start].chars().all(|c| !c.is_alphanumeric())
+    })
+}
+
+/// Findings of the rules in a file.
+fn scan(rules: &[CompiledRule], allow: &Regex, path: &Path, content: &str) -> Vec<Finding> {
+    let index = LineIndex::new(content);
+    let finding = |line: Option<usize>, column: Option<usize>, message: String| Finding {
+        location: path.display().to_string(),
+        line,
+        column,
+        message,
+    };
+    let mut findings = vec![];
+    for rule in rules.iter().filter(|r| r.applies(path)) {
+        let mut matched = false;
+        for m in rule.regex.find_iter(content) {
+            matched = true;
+            if rule.rule.required {
+                break;
+            }
+            let (line, column) = index.position(m.start());
+            let allowed = rule.rule.allow_comment
+                && (allow_comment(allow, index.line(line), &rule.rule.name).is_some()
+                    || line > 1
+                        && allow_comment(allow, index.line(line - 1), &rule.rule.name)
+                            == Some(true));
+            if !allowed {
+                findings.push(finding(
+                    Some(line),
+                    Some(column),
+                    rule.message("forbidden pattern"),
+                ));
+            }
+        }
+        if rule.rule.required && !matched {
+            findings.push(finding(
+                None,
+                None,
+                rule.message(&format!("missing `{}`", rule.regex.as_str())),
+            ));
+        }
+    }
+    findings.sort_by_key(|f| (f.line, f.column));
+    findings
+}
+
+impl Pattern {
+    fn compile(&self) -> Result<Vec<CompiledRule<'_>>, CheckError> {
+        self.rules
+            .iter()
+            .map(|rule| {
+                Ok(CompiledRule {
+                    rule,
+                    regex: Regex::new(&rule.regex)?,
+                    paths: Patterns::new(&rule.paths)?,
+                    exclude: Patterns::new(&rule.exclude)?,
+                })
+            })
+            .collect()
+    }
+    pub(crate) fn execute(&self, repository: &Path, fix: bool) -> Result<(), CheckError> {
+        if fix {
+            return Err(CheckError::NoFix);
+        }
+        let rules = self.compile()?;
+        let allow = Regex::new(ALLOW)?;
+        let files = scan::list(repository, self.scope, &self.include, &self.exclude)?;
+        let findings = scan::scan(repository, &files, |file, content| {
+            if !file.attributes.is_text(content) {
+                return vec![];
+            }
+            scan(
+                &rules,
+                &allow,
+                &file.path,
+                &String::from_utf8_lossy(content),
+            )
+        })?;
+        if !findings.is_empty() {
+            return Err(CheckError::Findings(findings));
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn scan_patterns() {
+    let check: Pattern = serde_yaml::from_str(
+        r"rules:
+  - name: no-dbg
+    regex: 'dbg!\('
+    message: Remove debugging macros
+    paths: ['*.rs']
+  - name: no-unwrap
+    regex: '\.unwrap\(\)'
+    exclude: [tests/]
+  - name: spdx
+    regex: SPDX-License-Identifier
+    required: true",
+    )
+    .unwrap();
+    let rules = check.compile().unwrap();
+    let allow = Regex::new(ALLOW).unwrap();
+    let content = "fn main() {
+    let x = dbg!(1); dbg!(x).unwrap();
+    dbg!(x); // checkalot: allow(no-dbg)
+    // checkalot: allow(no-dbg, no-unwrap)
+    dbg!(x).unwrap();
+}
+";
+    let findings: Vec<_> = scan(&rules, &allow, Path::new("src/main.rs"), content)
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    assert_eq!(
+        findings,
+        [
+            "src/main.rs: [spdx] missing `SPDX-License-Identifier`",
+            "src/main.rs:2:13: [no-dbg] Remove debugging macros",
+            "src/main.rs:2:22: [no-dbg] Remove debugging macros",
+            "src/main.rs:2:29: [no-unwrap] forbidden pattern"
+        ]
+    );
+    assert_eq!(
+        scan(&rules, &allow, Path::new("tests/a.py"), content).len(),
+        1
+    );
+}
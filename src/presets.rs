@@ -0,0 +1,314 @@
+//! Built-in groups of checks, expanded into `command` checks when loading the configuration.
+//!
+//! Presets evolve with checkalot, so that improvements reach all repositories using them.
+use std::path::{Path, PathBuf};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{Check, CheckEntry, CommandSpec};
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+#[serde(rename_all = "kebab-case", tag = "preset", deny_unknown_fields)]
+pub enum Preset {
+    /// `fmt`, `clippy`, `machete` and `deny` checks for a Rust crate or workspace.
+    RustStandard {
+        /// Directory of the crate or workspace. Repository root if left empty.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        folder: Option<PathBuf>,
+        /// Features enabled for clippy.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        features: Vec<String>,
+        /// Enable all features for clippy.
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        all_features: bool,
+        /// Lints denied by clippy.
+        #[serde(default = "default_deny")]
+        deny: Vec<String>,
+    },
+    /// `hakari` and `hakari-deps` checks, for workspaces using cargo-hakari.
+    RustWorkspaceHakari {
+        /// Directory of the workspace. Repository root if left empty.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        folder: Option<PathBuf>,
+    },
+    /// `prettier` check.
+    Prettier {
+        /// Directory where prettier is executed. Repository root if left empty.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        folder: Option<PathBuf>,
+        /// Files or globs to format.
+        #[serde(default = "default_files")]
+        files: Vec<String>,
+    },
+}
+
+fn default_deny() -> Vec<String> {
+    vec!["warnings".into()]
+}
+fn default_files() -> Vec<String> {
+    vec![".".into()]
+}
+
+impl Preset {
+    pub fn name(&self) -> &str {
+        match self {
+            Preset::RustStandard { .. } => "rust-standard",
+            Preset::RustWorkspaceHakari { .. } => "rust-workspace-hakari",
+            Preset::Prettier { .. } => "prettier",
+        }
+    }
+    fn checks(&self) -> Vec<Check> {
+        match self {
+            Preset::RustStandard {
+                folder,
+                features,
+                all_features,
+                deny,
+            } => {
+                let rust = ["**/*.rs", "**/Cargo.toml", "Cargo.lock"];
+                let mut flags = String::from("--workspace --all-targets");
+                if *all_features {
+                    flags.push_str(" --all-features");
+                } else if !features.is_empty() {
+                    flags.push_str(&format!(" --features {}", features.join(",")));
+                }
+                let lints: Vec<_> = deny.iter().map(|l| format!("-D {}", l)).collect();
+                let lints = lints.join(" ");
+                vec![
+                    command(folder, "fmt", "cargo fmt --all -- --check", &["**/*.rs"])
+                        .fix("cargo fmt --all"),
+                    // The fix command uses the same arguments, to avoid rebuilds.
+                    command(
+                        folder,
+                        "clippy",
+                        &format!("cargo clippy {} -- {}", flags, lints),
+                        &rust,
+                    )
+                    .fix(&format!(
+                        "cargo clippy {} --fix --allow-dirty --allow-staged -- {}",
+                        flags, lints
+                    )),
+                    command(folder, "machete", "cargo machete", &rust).fix_spec(
+                        CommandSpec::Complex {
+                            command: "cargo machete --fix".into(),
+                            success_statuses: vec![0, 1],
+                        },
+                    ),
+                    command(
+                        folder,
+                        "deny",
+                        "cargo deny --offline check licenses bans sources --hide-inclusion-graph",
+                        &["**/Cargo.toml", "Cargo.lock", "deny.toml"],
+                    )
+                    .version("cargo deny --version", ">=0.14"),
+                ]
+            }
+            Preset::RustWorkspaceHakari { folder } => vec![
+                command(
+                    folder,
+                    "hakari",
+                    "cargo hakari generate --diff",
+                    &["**/Cargo.toml"],
+                )
+                .fix("cargo hakari generate")
+                .version("cargo hakari --version", ">=0.9"),
+                command(
+                    folder,
+                    "hakari-deps",
+                    "cargo hakari manage-deps --dry-run",
+                    &["**/Cargo.toml"],
+                )
+                .fix("cargo hakari manage-deps")
+                .version("cargo hakari --version", ">=0.9"),
+            ],
+            Preset::Prettier { folder, files } => {
+                let files = shell_words::join(files);
+                vec![command(
+                    folder,
+                    "prettier",
+                    &format!("prettier --check {}", files),
+                    &[],
+                )
+                .fix(&format!("prettier --write {}", files))
+                .version("prettier --version", ">=3")]
+            }
+        }
+    }
+}
+
+/// Command check, whose name is prefixed with the folder if set, e.g. `crates/server/clippy`, so
+/// that a preset can be used for several folders.
+fn command(folder: &Option<PathBuf>, name: &str, command: &str, paths: &[&str]) -> Check {
+    let name = match folder {
+        Some(folder) => {
+            let prefix = folder
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            format!("{}/{}", prefix, name)
+        }
+        None => name.into(),
+    };
+    Check::Command {
+        name,
+        command: Some(CommandSpec::Simple(command.into())),
+        uses: None,
+        with: Default::default(),
+        fix_command: None,
+        folder: folder.clone(),
+        version_command: None,
+        version: None,
+        paths: paths
+            .iter()
+            .map(|p| match folder {
+                Some(folder) => Path::new(folder).join(p).to_string_lossy().into(),
+                None => p.to_string(),
+            })
+            .collect(),
+        matrix: Default::default(),
+        output: None,
+        env: Default::default(),
+        timeout: None,
+    }
+}
+
+impl Check {
+    fn fix(self, command: &str) -> Self {
+        self.fix_spec(CommandSpec::Simple(command.into()))
+    }
+    fn fix_spec(mut self, spec: CommandSpec) -> Self {
+        if let Check::Command { fix_command, .. } = &mut self {
+            *fix_command = Some(spec);
+        }
+        self
+    }
+    fn version(mut self, command: &str, requirement: &str) -> Self {
+        if let Check::Command {
+            version_command,
+            version,
+            ..
+        } = &mut self
+        {
+            *version_command = Some(CommandSpec::Simple(command.into()));
+            *version = Some(requirement.parse().unwrap());
+        }
+        self
+    }
+}
+
+/// Replace the presets by their checks, which inherit their tags.
+pub fn expand(checks: Vec<CheckEntry>) -> Vec<CheckEntry> {
+    checks
+        .into_iter()
+        .flat_map(|entry| match &entry.check {
+            Check::Preset(preset) => preset
+                .checks()
+                .into_iter()
+                .map(|check| CheckEntry {
+                    check,
+                    ..entry.clone()
+                })
+                .collect(),
+            _ => vec![entry],
+        })
+        .collect()
+}
+
+#[test]
+fn expand_presets() {
+    let config = crate::Config::from_source(
+        Path::new("checkalot.yaml"),
+        "checks:
+  - type: preset
+    preset: rust-standard
+    folder: rust
+    features: [a, b]
+    tags: [rust]
+  - type: preset
+    preset: prettier
+    files: [README.md, docs/*.md]",
+    )
+    .unwrap();
+    let checks = expand(config.checks);
+    assert_eq!(
+        checks.iter().map(CheckEntry::name).collect::<Vec<_>>(),
+        [
+            "rust/fmt",
+            "rust/clippy",
+            "rust/machete",
+            "rust/deny",
+            "prettier"
+        ]
+    );
+    assert_eq!(checks[1].tags, ["rust"]);
+    let Check::Command {
+        command,
+        fix_command,
+        paths,
+        ..
+    } = &checks[1].check
+    else {
+        panic!()
+    };
+    assert_eq!(
+        command.as_ref().unwrap().command(),
+        "cargo clippy --workspace --all-targets --features a,b -- -D warnings"
+    );
+    assert!(fix_command
+        .as_ref()
+        .unwrap()
+        .command()
+        .starts_with("cargo clippy --workspace --all-targets --features a,b --fix"));
+    assert_eq!(paths[0], "rust/**/*.rs");
+    let Check::Command { command, .. } = &checks[4].check else {
+        panic!()
+    };
+    assert_eq!(
+        command.as_ref().unwrap().command(),
+        "prettier --check README.md 'docs/*.md'"
+    );
+}
+
+#[test]
+fn preset_names() {
+    let resolve = |text: &str| {
+        let path = Path::new("checkalot.yaml");
+        let mut console = crate::ui::Console::new(crate::ui::Style::detect(true));
+        crate::Config::from_source(path, text)?.resolve(path, &mut console)
+    };
+    let config = resolve(
+        "checks:
+  - type: preset
+    preset: rust-workspace-hakari
+    folder: server
+  - type: preset
+    preset: rust-workspace-hakari
+    folder: client",
+    )
+    .unwrap();
+    assert_eq!(
+        config
+            .checks
+            .iter()
+            .map(CheckEntry::name)
+            .collect::<Vec<_>>(),
+        [
+            "server/hakari",
+            "server/hakari-deps",
+            "client/hakari",
+            "client/hakari-deps"
+        ]
+    );
+    let error = resolve(
+        "checks:
+  - type: command
+    name: hakari
+    command: cargo hakari generate --diff --quiet
+  - type: preset
+    preset: rust-workspace-hakari",
+    )
+    .unwrap_err();
+    assert!(error.to_string().contains("`hakari`"));
+}
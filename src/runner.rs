@@ -0,0 +1,202 @@
+//! Execution of the checks, independently of their presentation.
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::Context;
+use serde::Serialize;
+
+use crate::errors::{CheckError, ExitCode, Finding, RunCommandError};
+use crate::ui::Reporter;
+use crate::variables::Variables;
+use crate::Config;
+
+#[derive(Default)]
+pub struct Options {
+    /// Fix failing checks with their fix command.
+    pub fix: bool,
+    /// Continue after failures.
+    pub keep_going: bool,
+    /// If set, checks are only run if one of these files matches their `paths` or, for nested
+    /// configurations, is in their folder.
+    pub changed_files: Option<Vec<PathBuf>>,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Passed,
+    /// Failed, and then successfully fixed.
+    Fixed,
+    Failed,
+    Skipped,
+}
+
+#[derive(Serialize, Debug)]
+pub struct CheckReport {
+    pub name: String,
+    pub status: Status,
+    #[serde(serialize_with = "serialize_secs")]
+    pub duration: Duration,
+    /// Reason for skipping, or error message.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// Output of the failed command.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+    /// Problems reported by a built-in check.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub findings: Vec<Finding>,
+    #[serde(skip)]
+    error: Option<CheckError>,
+}
+
+/// Results of a run.
+#[derive(Serialize, Debug)]
+pub struct Report {
+    pub checks: Vec<CheckReport>,
+    #[serde(serialize_with = "serialize_secs")]
+    pub duration: Duration,
+}
+impl Report {
+    pub fn count(&self, status: Status) -> usize {
+        self.checks.iter().filter(|c| c.status == status).count()
+    }
+    pub fn ran_fix(&self) -> bool {
+        self.count(Status::Fixed) > 0
+    }
+    /// Save as JSON.
+    pub fn write(&self, path: &Path) -> anyhow::Result<()> {
+        let file = std::fs::File::create(path)
+            .with_context(|| format!("Failed to create report at {:?}", path))?;
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(())
+    }
+    /// Error for the first failed check, if any. Otherwise, returns `true` if at least one fix
+    /// ran.
+    pub fn into_result(self) -> anyhow::Result<bool> {
+        let ran_fix = self.ran_fix();
+        let failed: Vec<_> = self
+            .checks
+            .iter()
+            .filter(|c| c.status == Status::Failed)
+            .map(|c| format!("'{}'", c.name))
+            .collect();
+        let Some(error) = self.checks.into_iter().find_map(|c| c.error) else {
+            return Ok(ran_fix);
+        };
+        let mut message = if failed.len() > 1 {
+            format!("The checks {} have failed.", failed.join(", "))
+        } else {
+            format!("The check {} has failed.", failed[0])
+        };
+        if matches!(
+            error,
+            CheckError::RunCommand(RunCommandError::StatusCode { .. })
+        ) {
+            message.push_str(" Try running with --fix.");
+        }
+        Err(anyhow::Error::new(error).context(message))
+    }
+}
+
+fn serialize_secs<S: serde::Serializer>(duration: &Duration, s: S) -> Result<S::Ok, S::Error> {
+    s.serialize_f32(duration.as_secs_f32())
+}
+
+/// Run the checks sequentially, stopping at the first failure unless `keep_going` is set.
+/// If `fix` is set, failing checks are fixed with their fix command.
+pub fn run_checks(
+    config: &Config,
+    repository: &Path,
+    options: &Options,
+    reporter: &mut dyn Reporter,
+) -> anyhow::Result<Report> {
+    let n_checks = config.checks.len();
+    let start = std::time::Instant::now();
+
+    reporter.start(n_checks, repository);
+
+    let mut checks = vec![];
+
+    for (i, check) in config.checks.iter().enumerate() {
+        let start_check = std::time::Instant::now();
+        let mut report = CheckReport {
+            name: check.name().into(),
+            status: Status::Passed,
+            duration: Duration::ZERO,
+            message: None,
+            output: None,
+            findings: vec![],
+            error: None,
+        };
+
+        // Checks of nested configurations run in their folder.
+        let folder = repository.join(&check.folder);
+
+        if let Some(condition) = check.when.as_ref().filter(|c| !c.evaluate(&folder)) {
+            let reason = format!("`{}` is false", condition);
+            reporter.check_skipped(i, check.name(), &reason);
+            report.status = Status::Skipped;
+            report.message = Some(reason);
+            checks.push(report);
+            continue;
+        }
+
+        if let Some(changed_files) = &options.changed_files {
+            if !check.matches_changes(changed_files)? {
+                let reason = if check.paths().is_empty() {
+                    "no changes in its folder"
+                } else {
+                    "no changes in its paths"
+                };
+                reporter.check_skipped(i, check.name(), reason);
+                report.status = Status::Skipped;
+                report.message = Some(reason.into());
+                checks.push(report);
+                continue;
+            }
+        }
+
+        reporter.check_started(i, check.name());
+
+        let variables = Variables::new(repository, check.name(), config.bundle_dir());
+        let result = match check.check.execute(&folder, false, &variables) {
+            // Only attempt fixes for genuine failures, not e.g. for missing tools.
+            Err(e) if options.fix && e.exit_code() == ExitCode::CheckFailed => {
+                reporter.check_fixing(check.name());
+                report.status = Status::Fixed;
+                check.check.execute(&folder, true, &variables)
+            }
+            r => r,
+        };
+        report.duration = start_check.elapsed();
+        match result {
+            Ok(()) => {
+                reporter.check_passed(check.name(), report.duration);
+            }
+            Err(e) => {
+                reporter.check_failed(check.name(), &e, report.duration);
+                report.status = Status::Failed;
+                report.message = Some(e.to_string());
+                if let CheckError::RunCommand(RunCommandError::StatusCode { output, .. }) = &e {
+                    report.output = Some(output.clone());
+                }
+                if let CheckError::Findings(findings) = &e {
+                    report.findings.clone_from(findings);
+                }
+                report.error = Some(e);
+            }
+        }
+        let failed = report.status == Status::Failed;
+        checks.push(report);
+        if failed && !options.keep_going {
+            break;
+        }
+    }
+    let report = Report {
+        checks,
+        duration: start.elapsed(),
+    };
+    reporter.finish(&report);
+    Ok(report)
+}
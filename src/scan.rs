@@ -0,0 +1,371 @@
+//! Selection and reading of the files scanned by the built-in checks, which run in-process.
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use rayon::prelude::*;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::errors::{CheckError, Finding};
+
+/// Files scanned by a check.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+    /// Files tracked by git.
+    #[default]
+    Tracked,
+    /// Files staged for the next commit, with their staged content.
+    Staged,
+    /// Files that are not ignored by `.gitignore`, including untracked files.
+    All,
+}
+impl Scope {
+    pub(crate) fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Patterns in the `.gitignore` syntax, e.g. `*.pem` or `target/`, relative to the folder of the
+/// check.
+pub(crate) struct Patterns(Gitignore);
+impl Patterns {
+    pub fn new(patterns: &[String]) -> Result<Self, CheckError> {
+        let mut builder = GitignoreBuilder::new("");
+        for pattern in patterns {
+            builder.add_line(None, pattern)?;
+        }
+        Ok(Self(builder.build()?))
+    }
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+    /// Whether the file or one of its parent folders matches.
+    pub fn is_match(&self, path: &Path) -> bool {
+        self.0.matched_path_or_any_parents(path, false).is_ignore()
+    }
+}
+
+/// File to scan, relative to the folder of the check.
+pub(crate) struct File {
+    pub path: PathBuf,
+    pub attributes: Attributes,
+    staged: bool,
+}
+impl File {
+    /// Content of the file, from the index for staged files.
+    pub fn read(&self, repository: &Path) -> Result<Vec<u8>, CheckError> {
+        let error = |e| CheckError::ReadFile(self.path.clone(), e);
+        if self.staged {
+            let object = format!(":./{}", self.path.display());
+            duct::cmd!("git", "show", object)
+                .dir(repository)
+                .stdout_capture()
+                .stderr_null()
+                .run()
+                .map(|output| output.stdout)
+                .map_err(error)
+        } else {
+            std::fs::read(repository.join(&self.path)).map_err(error)
+        }
+    }
+    /// Location for the findings.
+    pub fn finding(&self, line: Option<usize>, message: impl Into<String>) -> Finding {
+        Finding {
+            location: self.path.display().to_string(),
+            line,
+            column: None,
+            message: message.into(),
+        }
+    }
+}
+
+/// Files of the scope, matching `include` (all files if empty) but not `exclude`, in
+/// alphabetical order.
+pub(crate) fn list(
+    repository: &Path,
+    scope: Scope,
+    include: &[String],
+    exclude: &[String],
+) -> Result<Vec<File>, CheckError> {
+    let git = |args: &[&str]| -> Result<Vec<PathBuf>, CheckError> {
+        Ok(crate::git::git(repository, args)?
+            .split('\0')
+            .filter(|p| !p.is_empty())
+            .map(PathBuf::from)
+            .collect())
+    };
+    let mut paths = match scope {
+        // Deleted files are still listed by `ls-files` until the deletion is staged.
+        Scope::Tracked => git(&["ls-files", "-z"])?
+            .into_iter()
+            .filter(|p| repository.join(p).is_file())
+            .collect(),
+        Scope::Staged => git(&[
+            "diff",
+            "--cached",
+            "--name-only",
+            "--relative",
+            "--diff-filter=d",
+            "-z",
+        ])?,
+        Scope::All => ignore::WalkBuilder::new(repository)
+            .hidden(false)
+            .filter_entry(|e| e.file_name() != ".git")
+            .build()
+            .filter_map(Result::ok)
+            .filter(|e| e.file_type().is_some_and(|t| t.is_file()))
+            .filter_map(|e| Some(e.path().strip_prefix(repository).ok()?.to_owned()))
+            .collect(),
+    };
+    paths.sort();
+    let include = Patterns::new(include)?;
+    let exclude = Patterns::new(exclude)?;
+    paths.retain(|p| (include.is_empty() || include.is_match(p)) && !exclude.is_match(p));
+    let attributes = attributes(repository, &paths)?;
+    Ok(paths
+        .into_iter()
+        .zip(attributes)
+        .map(|(path, attributes)| File {
+            path,
+            attributes,
+            staged: scope == Scope::Staged,
+        })
+        .collect())
+}
+
+/// Read the files and apply `scan` on their content, in parallel.
+pub(crate) fn scan<T: Send>(
+    repository: &Path,
+    files: &[File],
+    scan: impl Fn(&File, &[u8]) -> Vec<T> + Sync,
+) -> Result<Vec<T>, CheckError> {
+    let findings: Vec<Vec<T>> = files
+        .par_iter()
+        .map(|file| Ok(scan(file, &file.read(repository)?)))
+        .collect::<Result<_, CheckError>>()?;
+    Ok(findings.into_iter().flatten().collect())
+}
+
+/// Lines and columns of byte offsets in a text.
+pub(crate) struct LineIndex<'a> {
+    content: &'a str,
+    starts: Vec<usize>,
+}
+impl<'a> LineIndex<'a> {
+    pub fn new(content: &'a str) -> Self {
+        let starts = std::iter::once(0)
+            .chain(content.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        Self { content, starts }
+    }
+    /// 1-based line and column (in characters) of the offset.
+    pub fn position(&self, offset: usize) -> (usize, usize) {
+        let line = self.starts.partition_point(|s| *s <= offset) - 1;
+        let column = self.content[self.starts[line]..offset].chars().count() + 1;
+        (line + 1, column)
+    }
+    /// Content of a 1-based line, without line ending.
+    pub fn line(&self, line: usize) -> &'a str {
+        let start = self.starts[line - 1];
+        let end = self.starts.get(line).copied().unwrap_or(self.content.len());
+        self.content[start..end].trim_end_matches(['\n', '\r'])
+    }
+}
+
+/// Lines added since the merge base of a base revision and HEAD, by file.
+pub(crate) struct AddedLines(HashMap<PathBuf, Vec<(usize, usize)>>);
+impl AddedLines {
+    /// Committed and staged changes, unstaged changes unless the scope is `staged`, and
+    /// untracked files if the scope is `all`.
+    pub fn new(repository: &Path, base: &str, scope: Scope) -> Result<Self, CheckError> {
+        let git = |args: &[&str]| crate::git::git(repository, args);
+        let merge_base = git(&["merge-base", base, "HEAD"])?;
+        let mut args = vec![
+            "-c",
+            "core.quotePath=false",
+            "diff",
+            "-U0",
+            "--no-color",
+            "--no-prefix",
+            "--relative",
+        ];
+        if scope == Scope::Staged {
+            args.push("--cached");
+        }
+        args.push(merge_base.trim());
+        let mut lines: HashMap<PathBuf, Vec<_>> = HashMap::new();
+        let mut file = None;
+        for line in git(&args)?.lines() {
+            if let Some(path) = line.strip_prefix("+++ ") {
+                file = (path != "/dev/null").then(|| unquote(path));
+            } else if let (Some(file), Some(hunk)) = (&file, line.strip_prefix("@@ ")) {
+                // `@@ -start,count +start,count @@`, where the counts default to 1.
+                let Some(added) = hunk.split(' ').find_map(|r| r.strip_prefix('+')) else {
+                    continue;
+                };
+                let (start, count) = added.split_once(',').unwrap_or((added, "1"));
+                let (Ok(start), Ok(count)) = (start.parse::<usize>(), count.parse::<usize>())
+                else {
+                    continue;
+                };
+                if count > 0 {
+                    let ranges = lines.entry(file.clone()).or_default();
+                    ranges.push((start, start + count - 1));
+                }
+            }
+        }
+        if scope == Scope::All {
+            // Untracked files are entirely new.
+            let untracked = git(&["ls-files", "--others", "--exclude-standard", "-z"])?;
+            for path in untracked.split('\0').filter(|p| !p.is_empty()) {
+                lines.insert(path.into(), vec![(1, usize::MAX)]);
+            }
+        }
+        Ok(Self(lines))
+    }
+    pub fn contains_file(&self, path: &Path) -> bool {
+        self.0.contains_key(path)
+    }
+    pub fn contains(&self, path: &Path, line: usize) -> bool {
+        self.0
+            .get(path)
+            .is_some_and(|ranges| ranges.iter().any(|(s, e)| (*s..=*e).contains(&line)))
+    }
+}
+
+/// Path of a `+++` diff header. Paths with special characters are quoted with C-style escapes,
+/// and paths with spaces are followed by a tab.
+fn unquote(path: &str) -> PathBuf {
+    let Some(quoted) = path
+        .strip_prefix('"')
+        .and_then(|p| p.trim_end_matches('\t').strip_suffix('"'))
+    else {
+        return PathBuf::from(path.trim_end_matches('\t'));
+    };
+    let mut bytes = vec![];
+    let mut chars = quoted.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+            continue;
+        }
+        match chars.next() {
+            Some('n') => bytes.push(b'\n'),
+            Some('t') => bytes.push(b'\t'),
+            Some('r') => bytes.push(b'\r'),
+            Some('a') => bytes.push(7),
+            Some('b') => bytes.push(8),
+            Some('f') => bytes.push(12),
+            Some('v') => bytes.push(11),
+            // Octal escape of a byte, e.g. `\303\251` for `é`.
+            Some(c @ '0'..='3') => {
+                let octal: String = std::iter::once(c).chain(chars.by_ref().take(2)).collect();
+                bytes.push(u8::from_str_radix(&octal, 8).unwrap_or_default());
+            }
+            Some(c) => bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes()),
+            None => {}
+        }
+    }
+    PathBuf::from(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// Whether git treats a file as text, according to `.gitattributes`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Text {
+    /// Detected from the content.
+    Auto,
+    /// `text`, or `eol` set.
+    Always,
+    /// `binary`, `-text` or `-diff`.
+    Never,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Attributes {
+    pub text: Text,
+    /// `eol=crlf`: the file is checked out with CRLF line endings.
+    pub crlf: bool,
+}
+impl Attributes {
+    pub fn is_text(&self, content: &[u8]) -> bool {
+        match self.text {
+            Text::Auto => !is_binary(content),
+            Text::Always => true,
+            Text::Never => false,
+        }
+    }
+}
+
+/// Attributes of the files, in the same order.
+fn attributes(repository: &Path, paths: &[PathBuf]) -> Result<Vec<Attributes>, CheckError> {
+    if paths.is_empty() {
+        return Ok(vec![]);
+    }
+    let mut input = vec![];
+    for path in paths {
+        input.extend_from_slice(path.to_string_lossy().as_bytes());
+        input.push(0);
+    }
+    let output = crate::checks::run_expr(
+        "git",
+        duct::cmd!("git", "check-attr", "-z", "--stdin", "text", "diff", "eol")
+            .stdin_bytes(input)
+            .dir(repository),
+        &[0],
+    )
+    .map_err(|e| e.tool("git check-attr"))?;
+    // `<path> NUL <attribute> NUL <value> NUL`, for each file and attribute in order.
+    let values: Vec<_> = output.split('\0').skip(2).step_by(3).collect();
+    Ok(values
+        .chunks_exact(3)
+        .map(|values| {
+            let [text, diff, eol] = [values[0], values[1], values[2]];
+            Attributes {
+                text: if text == "unset" || diff == "unset" {
+                    Text::Never
+                } else if text == "set" || eol != "unspecified" {
+                    Text::Always
+                } else {
+                    Text::Auto
+                },
+                crlf: eol == "crlf",
+            }
+        })
+        .collect())
+}
+
+/// Whether the content looks binary, using the same heuristic as git: a NUL byte in the first
+/// 8000 bytes.
+pub(crate) fn is_binary(content: &[u8]) -> bool {
+    content.iter().take(8000).any(|b| *b == 0)
+}
+
+#[test]
+fn added_lines_special_paths() {
+    let folder = tempfile::tempdir().unwrap();
+    let repository = folder.path();
+    let git = |args: &[&str]| crate::git::git(repository, args).unwrap();
+    git(&["init", "-q"]);
+    git(&[
+        "-c",
+        "user.name=Test",
+        "-c",
+        "user.email=test@example.com",
+        "commit",
+        "-q",
+        "--allow-empty",
+        "-m",
+        "Initial commit",
+    ]);
+    let names = ["a b.txt", "é.txt", "q\"uote.txt", "tab\t.txt"];
+    for name in names {
+        std::fs::write(repository.join(name), "added\n").unwrap();
+    }
+    git(&["add", "."]);
+    let added = AddedLines::new(repository, "HEAD", Scope::Staged).unwrap();
+    for name in names {
+        assert!(added.contains(Path::new(name), 1), "{}", name);
+    }
+}
@@ -0,0 +1,202 @@
+//! Expressions selecting checks by name and tags, e.g. `tag:rust and not name:clippy*`.
+//!
+//! Atoms are `name:<glob>`, `tag:<glob>`, or a bare `<glob>` matching names, where `*` also matches
+//! `/`. They can be combined with `not`, `and`, `or` (by order of precedence) and parentheses.
+use std::collections::BTreeSet;
+
+use crate::validate::suggest;
+use crate::CheckEntry;
+
+#[derive(Debug)]
+pub enum Selector {
+    Name(Pattern),
+    Tag(Pattern),
+    Not(Box<Selector>),
+    And(Box<Selector>, Box<Selector>),
+    Or(Box<Selector>, Box<Selector>),
+}
+
+#[derive(Debug)]
+pub struct Pattern {
+    pattern: String,
+    matcher: globset::GlobMatcher,
+}
+impl Pattern {
+    fn new(pattern: &str) -> anyhow::Result<Self> {
+        Ok(Self {
+            pattern: pattern.into(),
+            matcher: globset::Glob::new(pattern)?.compile_matcher(),
+        })
+    }
+    fn is_match(&self, s: &str) -> bool {
+        self.matcher.is_match(s)
+    }
+}
+
+impl std::fmt::Display for Selector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Selector::Name(p) => write!(f, "name:{}", p.pattern),
+            Selector::Tag(p) => write!(f, "tag:{}", p.pattern),
+            Selector::Not(s) => write!(f, "not {}", s),
+            Selector::And(a, b) => write!(f, "({} and {})", a, b),
+            Selector::Or(a, b) => write!(f, "({} or {})", a, b),
+        }
+    }
+}
+
+fn tokenize(expression: &str) -> Vec<&str> {
+    let mut tokens = vec![];
+    for word in expression.split_whitespace() {
+        let mut rest = word;
+        while !rest.is_empty() {
+            let end = rest.find(['(', ')']).unwrap_or(rest.len());
+            if end == 0 {
+                tokens.push(&rest[..1]);
+                rest = &rest[1..];
+            } else {
+                tokens.push(&rest[..end]);
+                rest = &rest[end..];
+            }
+        }
+    }
+    tokens
+}
+
+struct Parser<'a> {
+    tokens: std::iter::Peekable<std::vec::IntoIter<&'a str>>,
+}
+impl Parser<'_> {
+    fn or(&mut self) -> anyhow::Result<Selector> {
+        let mut left = self.and()?;
+        while self.tokens.next_if_eq(&"or").is_some() {
+            left = Selector::Or(left.into(), self.and()?.into());
+        }
+        Ok(left)
+    }
+    fn and(&mut self) -> anyhow::Result<Selector> {
+        let mut left = self.unary()?;
+        while self.tokens.next_if_eq(&"and").is_some() {
+            left = Selector::And(left.into(), self.unary()?.into());
+        }
+        Ok(left)
+    }
+    fn unary(&mut self) -> anyhow::Result<Selector> {
+        match self.tokens.next() {
+            None => anyhow::bail!("Unexpected end of expression"),
+            Some("not") => Ok(Selector::Not(self.unary()?.into())),
+            Some("(") => {
+                let inner = self.or()?;
+                anyhow::ensure!(
+                    self.tokens.next() == Some(")"),
+                    "Missing closing parenthesis"
+                );
+                Ok(inner)
+            }
+            Some(token @ (")" | "and" | "or")) => anyhow::bail!("Unexpected `{}`", token),
+            Some(token) => {
+                if let Some(tag) = token.strip_prefix("tag:") {
+                    Ok(Selector::Tag(Pattern::new(tag)?))
+                } else {
+                    Ok(Selector::Name(Pattern::new(
+                        token.strip_prefix("name:").unwrap_or(token),
+                    )?))
+                }
+            }
+        }
+    }
+}
+
+impl std::str::FromStr for Selector {
+    type Err = anyhow::Error;
+    fn from_str(expression: &str) -> anyhow::Result<Self> {
+        let mut parser = Parser {
+            tokens: tokenize(expression).into_iter().peekable(),
+        };
+        let selector = parser.or()?;
+        if let Some(token) = parser.tokens.next() {
+            anyhow::bail!("Unexpected `{}`", token);
+        }
+        Ok(selector)
+    }
+}
+
+impl Selector {
+    pub fn matches(&self, check: &CheckEntry) -> bool {
+        match self {
+            Selector::Name(p) => p.is_match(check.name()),
+            Selector::Tag(p) => check.tags.iter().any(|t| p.is_match(t)),
+            Selector::Not(s) => !s.matches(check),
+            Selector::And(a, b) => a.matches(check) && b.matches(check),
+            Selector::Or(a, b) => a.matches(check) || b.matches(check),
+        }
+    }
+    /// Ensure that each atom matches at least one check, to catch typos.
+    pub fn validate(&self, checks: &[CheckEntry]) -> anyhow::Result<()> {
+        let (pattern, candidates): (_, BTreeSet<_>) = match self {
+            Selector::Name(p) => (p, checks.iter().map(CheckEntry::name).collect()),
+            Selector::Tag(p) => (
+                p,
+                checks
+                    .iter()
+                    .flat_map(|c| &c.tags)
+                    .map(String::as_str)
+                    .collect(),
+            ),
+            Selector::Not(s) => return s.validate(checks),
+            Selector::And(a, b) | Selector::Or(a, b) => {
+                a.validate(checks)?;
+                return b.validate(checks);
+            }
+        };
+        if !candidates.iter().any(|c| pattern.is_match(c)) {
+            let help = suggest(&pattern.pattern, candidates)
+                .map(|s| format!(" ({})", s))
+                .unwrap_or_default();
+            anyhow::bail!("`{}` does not match any check{}", self, help);
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn parse_and_match() {
+    let checks: Vec<CheckEntry> = serde_yaml::from_str(
+        "
+- type: command
+  name: clippy
+  command: cargo clippy
+  tags: [rust]
+- type: command
+  name: deny
+  command: cargo deny check
+  tags: [rust, network]
+- type: command
+  name: prettier-md
+  command: prettier --check README.md
+",
+    )
+    .unwrap();
+    let select = |expression: &str| -> Vec<&str> {
+        let selector: Selector = expression.parse().unwrap();
+        selector.validate(&checks).unwrap();
+        checks
+            .iter()
+            .filter(|c| selector.matches(c))
+            .map(CheckEntry::name)
+            .collect()
+    };
+    assert_eq!(
+        select("tag:rust and not tag:network or name:prettier*"),
+        ["clippy", "prettier-md"]
+    );
+    assert_eq!(select("not (tag:rust or deny)"), ["prettier-md"]);
+    let selector: Selector = "tag:rusty".parse().unwrap();
+    assert!(selector
+        .validate(&checks)
+        .unwrap_err()
+        .to_string()
+        .contains("did you mean `rust`"));
+    assert!("tag:rust and".parse::<Selector>().is_err());
+    assert!("(tag:rust".parse::<Selector>().is_err());
+}
@@ -0,0 +1,635 @@
+//! Built-in `syntax` check, parsing YAML, JSON and TOML files, and validating them against JSON
+//! Schemas.
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::errors::{CheckError, Finding};
+use crate::scan::{self, LineIndex, Patterns, Scope};
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Syntax {
+    /// Files to scan. Files with a `yaml`, `yml`, `json`, `jsonc` or `toml` extension are parsed.
+    /// JSON files are strict, except `jsonc` files and the configurations of tools known to accept
+    /// comments and trailing commas: `tsconfig*.json`, `jsconfig*.json`, `devcontainer.json` and
+    /// the files in `.vscode`.
+    #[serde(default, skip_serializing_if = "Scope::is_default")]
+    scope: Scope,
+    /// Patterns (in the `.gitignore` syntax) of the files to scan. All files if empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    include: Vec<String>,
+    /// Patterns (in the `.gitignore` syntax) of the files to ignore.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    exclude: Vec<String>,
+    /// JSON Schemas (in JSON or YAML, relative to the configuration folder) validating the files
+    /// matching a pattern (in the `.gitignore` syntax), e.g.
+    /// `{".github/workflows/*.yml": ci/workflow.schema.json}`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    schemas: BTreeMap<String, PathBuf>,
+}
+
+#[derive(Clone, Copy)]
+enum Format {
+    Yaml,
+    Json,
+    /// JSON with comments and trailing commas.
+    Jsonc,
+    Toml,
+}
+impl Format {
+    fn from_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?;
+        let jsonc = name.starts_with("tsconfig")
+            || name.starts_with("jsconfig")
+            || name.ends_with("devcontainer.json")
+            || path.parent().is_some_and(|p| p.ends_with(".vscode"));
+        match path.extension()?.to_str()? {
+            "yaml" | "yml" => Some(Self::Yaml),
+            "json" if jsonc => Some(Self::Jsonc),
+            "json" => Some(Self::Json),
+            "jsonc" => Some(Self::Jsonc),
+            "toml" => Some(Self::Toml),
+            _ => None,
+        }
+    }
+}
+
+/// Parsed document. YAML documents keep their tags, e.g. `!reference`, which have no JSON
+/// equivalent.
+enum Document {
+    Yaml(serde_yaml::Value),
+    Json(serde_json::Value),
+}
+impl Document {
+    /// Value to validate against a JSON Schema, without the YAML tags and with the YAML merge
+    /// keys (`<<`) resolved.
+    fn into_json(self) -> Result<serde_json::Value, String> {
+        fn untagged(value: serde_yaml::Value) -> serde_yaml::Value {
+            match value {
+                serde_yaml::Value::Tagged(tagged) => untagged(tagged.value),
+                serde_yaml::Value::Sequence(values) => {
+                    serde_yaml::Value::Sequence(values.into_iter().map(untagged).collect())
+                }
+                serde_yaml::Value::Mapping(mapping) => serde_yaml::Value::Mapping(
+                    mapping
+                        .into_iter()
+                        .map(|(k, v)| (untagged(k), untagged(v)))
+                        .collect(),
+                ),
+                value => value,
+            }
+        }
+        match self {
+            Document::Yaml(value) => {
+                let mut value = untagged(value);
+                value.apply_merge().map_err(|e| e.to_string())?;
+                serde_json::to_value(value).map_err(|e| e.to_string())
+            }
+            Document::Json(value) => Ok(value),
+        }
+    }
+}
+
+/// Replace the comments and trailing commas of JSONC with spaces, keeping the lines.
+fn strip_jsonc(content: &str) -> String {
+    let mut out = String::with_capacity(content.len());
+    let mut chars = content.chars().peekable();
+    // Index in `out` of the last comma outside strings, while only whitespace follows.
+    let mut comma = None;
+    let blank = |c: char| if c == '\n' { '\n' } else { ' ' };
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                comma = None;
+                out.push(c);
+                while let Some(c) = chars.next() {
+                    out.push(c);
+                    match c {
+                        '\\' => out.extend(chars.next()),
+                        '"' => break,
+                        _ => {}
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                out.push(' ');
+                while let Some(c) = chars.next_if(|c| *c != '\n') {
+                    out.push(blank(c));
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                out.push(' ');
+                out.push(blank(chars.next().unwrap()));
+                let mut previous = ' ';
+                for c in chars.by_ref() {
+                    out.push(blank(c));
+                    if previous == '*' && c == '/' {
+                        break;
+                    }
+                    previous = c;
+                }
+            }
+            ',' => {
+                comma = Some(out.len());
+                out.push(c);
+            }
+            '}' | ']' => {
+                if let Some(i) = comma.take() {
+                    out.replace_range(i..i + 1, " ");
+                }
+                out.push(c);
+            }
+            c => {
+                if !c.is_whitespace() {
+                    comma = None;
+                }
+                out.push(c);
+            }
+        }
+    }
+    out
+}
+
+/// Syntax error, with its 1-based line and column.
+struct Error {
+    message: String,
+    position: Option<(usize, usize)>,
+}
+
+/// Remove the ` at line X column Y` suffix of serde errors, reported separately.
+fn strip_location(message: String) -> String {
+    match message.rfind(" at line ") {
+        Some(i) => message[..i].into(),
+        None => message,
+    }
+}
+
+/// Parse the documents of the content (several for YAML streams).
+fn parse(format: Format, content: &str) -> Result<Vec<Document>, Error> {
+    let json = |content: &str| {
+        serde_json::from_str(content)
+            .map(|value| vec![Document::Json(value)])
+            .map_err(|e| Error {
+                position: Some((e.line(), e.column())),
+                message: strip_location(e.to_string()),
+            })
+    };
+    match format {
+        Format::Yaml => serde_yaml::Deserializer::from_str(content)
+            .map(|document| {
+                serde_yaml::Value::deserialize(document)
+                    .map(Document::Yaml)
+                    .map_err(|e| Error {
+                        position: e.location().map(|l| (l.line(), l.column())),
+                        message: strip_location(e.to_string()),
+                    })
+            })
+            .collect(),
+        Format::Json => json(content),
+        Format::Jsonc => json(&strip_jsonc(content)),
+        Format::Toml => toml::from_str(content)
+            .map(|value| vec![Document::Json(value)])
+            .map_err(|e| Error {
+                position: e
+                    .span()
+                    .map(|span| LineIndex::new(content).position(span.start)),
+                message: e.message().trim().replace('\n', ": "),
+            }),
+    }
+}
+
+/// Offsets of the start of the documents of a YAML stream, after their `---` marker.
+fn yaml_documents(content: &str) -> Vec<usize> {
+    let mut starts = vec![0];
+    let mut markers = 0;
+    // Whether the stream has content before its first marker.
+    let mut content_before = false;
+    let mut offset = 0;
+    for line in content.split_inclusive('\n') {
+        let text = line.trim_end();
+        if text == "---" || text.starts_with("--- ") {
+            // Nothing before the first marker does not make a document.
+            if markers == 0 && !content_before {
+                starts.clear();
+            }
+            markers += 1;
+            starts.push(offset + if text == "---" { line.len() } else { 4 });
+        } else if markers == 0 && !text.is_empty() && !text.starts_with('#') {
+            content_before = true;
+        }
+        offset += line.len();
+    }
+    starts
+}
+
+fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+/// Whether the text starts a block sequence item.
+fn is_item(text: &str) -> bool {
+    text == "-" || text.starts_with("- ")
+}
+
+/// Offset in `text` of the value of `key`, if `text` starts with this key of a block mapping.
+fn key_value(text: &str, key: &str) -> Option<usize> {
+    let rest = [("\"", "\""), ("'", "'"), ("", "")]
+        .into_iter()
+        .find_map(|(open, close)| {
+            text.strip_prefix(open)?
+                .strip_prefix(key)?
+                .strip_prefix(close)
+        })?;
+    let rest = rest.trim_start_matches(' ').strip_prefix(':')?;
+    (rest.is_empty() || rest.starts_with([' ', '\t'])).then(|| text.len() - rest.len())
+}
+
+/// Offset of the value at the JSON pointer in a YAML document, or of its deepest parent found.
+/// Only block collections are walked: values in flow collections or merged with `<<` are located
+/// at their parent.
+fn locate_yaml(document: &str, pointer: &[String]) -> usize {
+    // Lines with their offset, without blank lines and comments.
+    let lines: Vec<(usize, &str)> = document
+        .split_inclusive('\n')
+        .scan(0, |offset, line| {
+            let start = *offset;
+            *offset += line.len();
+            Some((start, line.trim_end()))
+        })
+        .filter(|(_, line)| {
+            let line = line.trim_start();
+            !line.is_empty() && !line.starts_with('#')
+        })
+        .collect();
+    let Some((_, first)) = lines.first() else {
+        return 0;
+    };
+    // Node: line, column, and end line (exclusive).
+    let (mut line, mut column, mut end) = (0, indentation(first), lines.len());
+    let lines = &lines;
+    // Lines of the node starting at the column.
+    let entries = |line: usize, column: usize, end: usize| {
+        std::iter::once(line)
+            .chain((line + 1..end).filter(move |l| indentation(lines[*l].1) == column))
+    };
+    for segment in pointer {
+        let text = &lines[line].1[column..];
+        let (entry, value, entry_end) = if is_item(text) {
+            let Some(item) = segment.parse().ok().and_then(|i| {
+                entries(line, column, end)
+                    .filter(|l| is_item(&lines[*l].1[column..]))
+                    .nth(i)
+            }) else {
+                break;
+            };
+            let item_end = (item + 1..end)
+                .find(|l| indentation(lines[*l].1) <= column)
+                .unwrap_or(end);
+            (item, column + 1, item_end)
+        } else {
+            let Some((key, value)) = entries(line, column, end)
+                .find_map(|l| Some((l, column + key_value(&lines[l].1[column..], segment)?)))
+            else {
+                break;
+            };
+            // Sequences can be indented like the key of their mapping.
+            let key_end = (key + 1..end)
+                .find(|l| {
+                    let indentation = indentation(lines[*l].1);
+                    indentation < column
+                        || (indentation == column && !is_item(&lines[*l].1[column..]))
+                })
+                .unwrap_or(end);
+            (key, value, key_end)
+        };
+        let rest = &lines[entry].1[value..];
+        let trimmed = rest.trim_start();
+        if trimmed.is_empty() || trimmed.starts_with(['&', '!', '#']) {
+            // Value on the next lines.
+            if entry + 1 >= entry_end {
+                (line, column) = (entry, value);
+                break;
+            }
+            (line, column, end) = (entry + 1, indentation(lines[entry + 1].1), entry_end);
+        } else {
+            (line, column, end) = (entry, value + rest.len() - trimmed.len(), entry_end);
+        }
+    }
+    lines[line].0 + column
+}
+
+/// Offset of the value at the JSON pointer in a JSON text without comments, or of its deepest
+/// parent found.
+fn locate_json(text: &str, pointer: &[String]) -> usize {
+    let bytes = text.as_bytes();
+    let skip_whitespace = |mut i: usize| {
+        while bytes.get(i).is_some_and(u8::is_ascii_whitespace) {
+            i += 1;
+        }
+        i
+    };
+    // End of the string starting at `i`.
+    let skip_string = |mut i: usize| {
+        i += 1;
+        while let Some(b) = bytes.get(i) {
+            i += if *b == b'\\' { 2 } else { 1 };
+            if *b == b'"' {
+                break;
+            }
+        }
+        i
+    };
+    // End of the value starting at `i`.
+    let skip_value = |mut i: usize| {
+        let mut depth = 0;
+        while let Some(b) = bytes.get(i) {
+            match b {
+                b'"' => {
+                    i = skip_string(i);
+                    continue;
+                }
+                b'{' | b'[' => depth += 1,
+                b'}' | b']' if depth == 0 => break,
+                b'}' | b']' => depth -= 1,
+                b',' if depth == 0 => break,
+                _ => {}
+            }
+            i += 1;
+        }
+        i
+    };
+    // Start of the next element after the one ending at `i`.
+    let next = |i: usize| {
+        let i = skip_whitespace(i);
+        (bytes.get(i) == Some(&b',')).then(|| skip_whitespace(i + 1))
+    };
+    let mut position = skip_whitespace(0);
+    'segments: for segment in pointer {
+        match bytes.get(position) {
+            Some(b'{') => {
+                let mut i = skip_whitespace(position + 1);
+                while bytes.get(i) == Some(&b'"') {
+                    let key_end = skip_string(i);
+                    let key: Option<String> = serde_json::from_str(&text[i..key_end]).ok();
+                    let value = skip_whitespace(skip_whitespace(key_end) + 1);
+                    if key.as_ref() == Some(segment) {
+                        position = value;
+                        continue 'segments;
+                    }
+                    let Some(n) = next(skip_value(value)) else {
+                        break;
+                    };
+                    i = n;
+                }
+                break;
+            }
+            Some(b'[') => {
+                let Ok(index) = segment.parse::<usize>() else {
+                    break;
+                };
+                let mut i = skip_whitespace(position + 1);
+                for _ in 0..index {
+                    let Some(n) = next(skip_value(i)) else {
+                        break 'segments;
+                    };
+                    i = n;
+                }
+                position = i;
+            }
+            _ => break,
+        }
+    }
+    position
+}
+
+/// Offset of the value at the JSON pointer in a TOML document, or of its deepest parent with a
+/// location, e.g. not for tables implied by dotted keys.
+fn locate_toml(content: &str, pointer: &[String]) -> Option<usize> {
+    let document = toml_edit::ImDocument::parse(content).ok()?;
+    let mut item = document.as_item();
+    let mut position = None;
+    for segment in pointer {
+        let next = match segment.parse::<usize>() {
+            Ok(index) => item.get(index),
+            Err(_) => item.get(segment.as_str()),
+        };
+        let Some(next) = next else {
+            break;
+        };
+        item = next;
+        position = item.span().map(|span| span.start).or(position);
+    }
+    position
+}
+
+/// Line and column of the value at the JSON pointer in the given document of the content.
+fn locate(
+    format: Format,
+    content: &str,
+    document: usize,
+    pointer: &[String],
+) -> Option<(usize, usize)> {
+    match format {
+        Format::Yaml => {
+            let start = *yaml_documents(content).get(document).unwrap_or(&0);
+            let offset = start + locate_yaml(&content[start..], pointer);
+            Some(LineIndex::new(content).position(offset))
+        }
+        Format::Json => Some(LineIndex::new(content).position(locate_json(content, pointer))),
+        Format::Jsonc => {
+            let stripped = strip_jsonc(content);
+            Some(LineIndex::new(&stripped).position(locate_json(&stripped, pointer)))
+        }
+        Format::Toml => {
+            let offset = locate_toml(content, pointer).unwrap_or(0);
+            Some(LineIndex::new(content).position(offset))
+        }
+    }
+}
+
+impl Syntax {
+    fn schemas(
+        &self,
+        repository: &Path,
+    ) -> Result<Vec<(Patterns, jsonschema::Validator)>, CheckError> {
+        self.schemas
+            .iter()
+            .map(|(pattern, path)| {
+                let invalid = |message: String| CheckError::InvalidSchema(path.clone(), message);
+                let content = std::fs::read_to_string(repository.join(path))
+                    .map_err(|e| CheckError::ReadFile(path.clone(), e))?;
+                let format = Format::from_path(path).unwrap_or(Format::Json);
+                let schema = parse(format, &content)
+                    .map_err(|e| invalid(e.message))?
+                    .pop()
+                    .ok_or_else(|| invalid("empty file".into()))?
+                    .into_json()
+                    .map_err(invalid)?;
+                let validator =
+                    jsonschema::validator_for(&schema).map_err(|e| invalid(e.to_string()))?;
+                Ok((Patterns::new(std::slice::from_ref(pattern))?, validator))
+            })
+            .collect()
+    }
+    pub(crate) fn execute(&self, repository: &Path, fix: bool) -> Result<(), CheckError> {
+        if fix {
+            return Err(CheckError::NoFix);
+        }
+        let schemas = self.schemas(repository)?;
+        let files = scan::list(repository, self.scope, &self.include, &self.exclude)?;
+        let findings = scan::scan(repository, &files, |file, content| {
+            let Some(format) = Format::from_path(&file.path) else {
+                return vec![];
+            };
+            let finding = |position: Option<(usize, usize)>, message| Finding {
+                location: file.path.display().to_string(),
+                line: position.map(|p| p.0),
+                column: position.map(|p| p.1),
+                message,
+            };
+            let content = match std::str::from_utf8(content) {
+                Ok(content) => content,
+                Err(_) => return vec![finding(None, "invalid UTF-8".into())],
+            };
+            let documents = match parse(format, content) {
+                Ok(documents) => documents,
+                Err(e) => return vec![finding(e.position, e.message)],
+            };
+            let schemas: Vec<_> = schemas
+                .iter()
+                .filter(|(patterns, _)| patterns.is_match(&file.path))
+                .map(|(_, validator)| validator)
+                .collect();
+            if schemas.is_empty() {
+                return vec![];
+            }
+            let documents = match documents
+                .into_iter()
+                .map(Document::into_json)
+                .collect::<Result<Vec<_>, _>>()
+            {
+                Ok(documents) => documents,
+                Err(e) => return vec![finding(None, e)],
+            };
+            let mut findings = vec![];
+            for validator in schemas {
+                for (i, document) in documents.iter().enumerate() {
+                    for error in validator.iter_errors(document) {
+                        let path = error.instance_path().to_string();
+                        let pointer: Vec<_> = path
+                            .split('/')
+                            .skip(1)
+                            .map(|s| s.replace("~1", "/").replace("~0", "~"))
+                            .collect();
+                        let position = locate(format, content, i, &pointer);
+                        let path = if path.is_empty() { "/".into() } else { path };
+                        findings.push(finding(position, format!("{}: {}", path, error)));
+                    }
+                }
+            }
+            findings
+        })?;
+        if !findings.is_empty() {
+            return Err(CheckError::Findings(findings));
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn syntax_errors() {
+    let error = |format, content| {
+        let error = parse(format, content).err().unwrap();
+        (error.position, error.message)
+    };
+    assert_eq!(
+        error(Format::Yaml, "a: 1\n---\nb: [\n"),
+        (Some((4, 1)), "did not find expected node content".into())
+    );
+    assert_eq!(
+        error(Format::Json, "{\n  \"a\": 1,\n}"),
+        (Some((3, 1)), "trailing comma".into())
+    );
+    assert_eq!(
+        error(Format::Toml, "[a]\nb = \n"),
+        (Some((2, 5)), "invalid string: expected `\"`, `'`".into())
+    );
+    assert_eq!(
+        parse(Format::Yaml, "a: 1\n---\nb: 2\n").ok().unwrap().len(),
+        2
+    );
+}
+
+#[test]
+fn yaml_tags_and_jsonc() {
+    let documents = parse(Format::Yaml, "script:\n  - !reference [.setup, script]\n")
+        .ok()
+        .unwrap();
+    let json = documents.into_iter().next().unwrap().into_json().unwrap();
+    assert_eq!(json, serde_json::json!({"script": [[".setup", "script"]]}));
+
+    let jsonc = "{\n  // Comment\n  \"a\": \"//\", /* b */\n  \"c\": [1, 2,],\n}\n";
+    assert!(parse(Format::Json, jsonc).is_err());
+    let Document::Json(json) = parse(Format::Jsonc, jsonc).ok().unwrap().remove(0) else {
+        panic!("JSON document expected");
+    };
+    assert_eq!(json, serde_json::json!({"a": "//", "c": [1, 2]}));
+    assert!(matches!(
+        Format::from_path(Path::new("web/tsconfig.app.json")),
+        Some(Format::Jsonc)
+    ));
+    assert!(matches!(
+        Format::from_path(Path::new(".vscode/settings.json")),
+        Some(Format::Jsonc)
+    ));
+}
+
+#[test]
+fn schema_locations() {
+    let pointer = |path: &str| -> Vec<String> { path.split('/').skip(1).map(Into::into).collect() };
+    let yaml = "a: 1
+---
+# Comment
+jobs:
+  build:
+    steps:
+    - run: a
+    - uses: 2
+      with: {b: 1}
+  test: &test
+    image: rust
+  lint:
+    <<: *test
+";
+    let position = |document, path| locate(Format::Yaml, yaml, document, &pointer(path));
+    assert_eq!(position(0, "/a"), Some((1, 4)));
+    assert_eq!(position(1, "/jobs/build/steps/1/uses"), Some((8, 13)));
+    assert_eq!(position(1, "/jobs/build/steps/1"), Some((8, 7)));
+    assert_eq!(position(1, "/jobs/build/steps/1/with/b"), Some((9, 13)));
+    assert_eq!(position(1, "/jobs/missing"), Some((5, 3)));
+    // Merged values are located at their parent.
+    assert_eq!(position(1, "/jobs/lint/image"), Some((13, 5)));
+    let documents = parse(Format::Yaml, yaml).ok().unwrap();
+    let json = documents.into_iter().nth(1).unwrap().into_json().unwrap();
+    assert_eq!(json["jobs"]["lint"], serde_json::json!({"image": "rust"}));
+
+    let yaml = "---\nsteps:\n- a\n- b: 1\n";
+    assert_eq!(
+        locate(Format::Yaml, yaml, 0, &pointer("/steps/1/b")),
+        Some((4, 6))
+    );
+
+    let json = "{\n  \"a\": [1, {\"b\": true}]\n}";
+    assert_eq!(
+        locate(Format::Json, json, 0, &pointer("/a/1/b")),
+        Some((2, 18))
+    );
+    let toml = "[package]\nname = 1\n\n[[bin]]\nname = \"a\"\n";
+    assert_eq!(
+        locate(Format::Toml, toml, 0, &pointer("/bin/0/name")),
+        Some((5, 8))
+    );
+}
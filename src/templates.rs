@@ -0,0 +1,210 @@
+//! Parameterised check bodies defined in `templates`, used by checks with `uses` and `with`.
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::matrix::substitute;
+use crate::validate::suggest;
+use crate::{Check, CheckEntry, CommandSpec, Config};
+
+/// Fields of a command check, with `{param}` placeholders in `command`, `fix_command`,
+/// `version_command`, `folder` and `output`. The fields set on the checks take precedence.
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Template {
+    /// Parameters, with their default value. Parameters without default are required.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    params: BTreeMap<String, Option<String>>,
+    command: CommandSpec,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fix_command: Option<CommandSpec>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version_command: Option<CommandSpec>,
+    #[schemars(with = "Option<String>")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version: Option<semver::VersionReq>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    folder: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    output: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    paths: Vec<String>,
+}
+
+impl Config {
+    /// Fill the checks using templates with their body.
+    pub(crate) fn apply_templates(
+        &self,
+        checks: Vec<CheckEntry>,
+    ) -> anyhow::Result<Vec<CheckEntry>> {
+        checks
+            .into_iter()
+            .map(|mut entry| {
+                let name = entry.name().to_string();
+                let Check::Command {
+                    command,
+                    fix_command,
+                    version_command,
+                    version,
+                    folder,
+                    output,
+                    paths,
+                    uses,
+                    with,
+                    ..
+                } = &mut entry.check
+                else {
+                    return Ok(entry);
+                };
+                let Some(uses) = uses.take() else {
+                    return Ok(entry);
+                };
+                let Some(template) = self.templates.get(&uses) else {
+                    let help = suggest(&uses, self.templates.keys().map(String::as_str))
+                        .map(|s| format!(" ({})", s))
+                        .unwrap_or_default();
+                    anyhow::bail!(
+                        "The check `{}` uses an unknown template `{}`{}",
+                        name,
+                        uses,
+                        help
+                    );
+                };
+                let mut values = BTreeMap::new();
+                for (param, value) in std::mem::take(with) {
+                    if !template.params.contains_key(&param) {
+                        let help = suggest(&param, template.params.keys().map(String::as_str))
+                            .map(|s| format!(" ({})", s))
+                            .unwrap_or_default();
+                        anyhow::bail!(
+                            "The check `{}` sets the unknown parameter `{}` of the template `{}`{}",
+                            name,
+                            param,
+                            uses,
+                            help
+                        );
+                    }
+                    values.insert(param, value);
+                }
+                for (param, default) in &template.params {
+                    if !values.contains_key(param) {
+                        let value = default.clone().ok_or_else(|| {
+                            anyhow::anyhow!(
+                                "The check `{}` is missing the parameter `{}` of the template `{}`",
+                                name,
+                                param,
+                                uses
+                            )
+                        })?;
+                        values.insert(param.clone(), value);
+                    }
+                }
+                let values: Vec<_> = values
+                    .iter()
+                    .map(|(k, v)| (k.as_str(), v.as_str()))
+                    .collect();
+                let path = |p: &PathBuf| PathBuf::from(substitute(&p.to_string_lossy(), &values));
+
+                *command = Some(
+                    command
+                        .as_ref()
+                        .unwrap_or(&template.command)
+                        .substitute(&values),
+                );
+                *fix_command = fix_command
+                    .as_ref()
+                    .or(template.fix_command.as_ref())
+                    .map(|c| c.substitute(&values));
+                *version_command = version_command
+                    .as_ref()
+                    .or(template.version_command.as_ref())
+                    .map(|c| c.substitute(&values));
+                *folder = folder.as_ref().or(template.folder.as_ref()).map(path);
+                *output = output.as_ref().or(template.output.as_ref()).map(path);
+                if version.is_none() {
+                    version.clone_from(&template.version);
+                }
+                if paths.is_empty() {
+                    paths.clone_from(&template.paths);
+                }
+                Ok(entry)
+            })
+            .collect()
+    }
+}
+
+#[test]
+fn apply_templates() {
+    let config = Config::from_source(
+        std::path::Path::new("checkalot.yaml"),
+        "templates:
+  cargo-tool:
+    params:
+      tool:
+      args: ''
+    command: cargo {tool} {args}
+    fix_command: cargo {tool} --fix
+    version_command: cargo {tool} --version
+checks:
+  - type: command
+    name: group-imports
+    uses: cargo-tool
+    with: {tool: group-imports}
+    version: '>=0.1.3'
+  - type: command
+    name: machete
+    uses: cargo-tool
+    with: {tool: machete, args: --with-metadata}
+    fix_command: cargo machete --fix --with-metadata",
+    )
+    .unwrap();
+    let checks = config.apply_templates(config.checks.clone()).unwrap();
+    let commands: Vec<_> = checks
+        .iter()
+        .map(|c| match &c.check {
+            Check::Command {
+                command: Some(command),
+                fix_command: Some(fix_command),
+                version_command: Some(version_command),
+                ..
+            } => [command, fix_command, version_command].map(|c| c.command().as_str()),
+            _ => panic!(),
+        })
+        .collect();
+    assert_eq!(
+        commands,
+        [
+            [
+                "cargo group-imports ",
+                "cargo group-imports --fix",
+                "cargo group-imports --version"
+            ],
+            [
+                "cargo machete --with-metadata",
+                "cargo machete --fix --with-metadata",
+                "cargo machete --version"
+            ]
+        ]
+    );
+
+    let config = Config::from_source(
+        std::path::Path::new("checkalot.yaml"),
+        "templates:
+  cargo-tool:
+    params: {tool: }
+    command: cargo {tool}
+checks:
+  - type: command
+    name: machete
+    uses: cargo-tool
+    with: {tol: machete}",
+    )
+    .unwrap();
+    assert!(config
+        .apply_templates(config.checks.clone())
+        .unwrap_err()
+        .to_string()
+        .contains("did you mean `tool`?"));
+}
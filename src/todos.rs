@@ -0,0 +1,262 @@
+//! Built-in `todos` check, requiring TODO-style comments to reference an issue.
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::errors::{CheckError, Finding};
+use crate::scan::{self, AddedLines, LineIndex, Scope};
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Todos {
+    /// Files to scan.
+    #[serde(default, skip_serializing_if = "Scope::is_default")]
+    scope: Scope,
+    /// Patterns (in the `.gitignore` syntax) of the files to scan. All files if empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    include: Vec<String>,
+    /// Patterns (in the `.gitignore` syntax) of the files to ignore.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    exclude: Vec<String>,
+    /// Tags to find, matched as uppercase words in comments: after `//`, `#`, `/*`, `--`,
+    /// `<!--` or `;` on the same line, or at the start of a line of a `/* */` block starting with
+    /// `*`. Tags in string literals or prose are ignored, as well as Markdown headings: only
+    /// `<!--` starts comments in Markdown files.
+    #[serde(default = "default_tags")]
+    tags: Vec<String>,
+    /// Regular expression for the reference in parentheses after the tag, e.g. `123` in
+    /// `TODO(#123)` or `JIRA-45` in `TODO(JIRA-45)`.
+    #[serde(default = "default_reference")]
+    reference: String,
+    /// Only report the comments on lines added since the merge base with this revision, e.g.
+    /// `origin/main`, so that existing comments are tolerated.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    base: Option<String>,
+    /// Write an inventory of all the comments, grouped by tag and file, at this location
+    /// (relative to the configuration folder).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    inventory: Option<PathBuf>,
+}
+
+fn default_tags() -> Vec<String> {
+    ["TODO", "FIXME", "XXX", "HACK"].map(String::from).into()
+}
+fn default_reference() -> String {
+    r"^(#\d+|[A-Z][A-Z0-9]+-\d+)$".into()
+}
+
+/// Comment with a tag.
+#[derive(Debug, PartialEq, Eq)]
+struct Todo {
+    tag: String,
+    reference: Option<String>,
+    line: usize,
+    column: usize,
+    text: String,
+}
+
+/// Regular expressions finding the comments, and the tags in them.
+struct Finder {
+    comments: Regex,
+    /// Comments of Markdown files, where `#` starts headings.
+    markdown: Regex,
+    tags: Regex,
+}
+
+impl Finder {
+    fn find(&self, content: &str, markdown: bool) -> Vec<Todo> {
+        let index = LineIndex::new(content);
+        let comments = if markdown {
+            &self.markdown
+        } else {
+            &self.comments
+        };
+        comments
+            .find_iter(content)
+            .flat_map(|comment| {
+                self.tags
+                    .captures_iter(comment.as_str())
+                    .map(move |captures| (comment.start(), captures))
+            })
+            .map(|(offset, captures)| {
+                let start = offset + captures.get(1).unwrap().start();
+                let (line, column) = index.position(start);
+                Todo {
+                    tag: captures[1].into(),
+                    reference: captures.get(2).map(|r| r.as_str().into()),
+                    line,
+                    column,
+                    text: content[start..]
+                        .lines()
+                        .next()
+                        .unwrap_or_default()
+                        .trim()
+                        .into(),
+                }
+            })
+            .collect()
+    }
+}
+
+impl Todos {
+    fn finder(&self) -> Result<Finder, regex::Error> {
+        let tags: Vec<_> = self.tags.iter().map(|t| regex::escape(t)).collect();
+        // The comment marker starts the line or follows a space, e.g. not `$#` or `a;`.
+        let marker = r"(?:^[ \t]*\*|(?:^|[ \t])(?://|#|/\*|--|<!--|;))";
+        Ok(Finder {
+            comments: Regex::new(&format!(r"(?m){}[^\n]*", marker))?,
+            markdown: Regex::new(r"<!--[^\n]*")?,
+            tags: Regex::new(&format!(r"\b({})\b(?:\(([^)]*)\))?", tags.join("|")))?,
+        })
+    }
+    fn write_inventory(
+        &self,
+        path: &Path,
+        todos: &[(PathBuf, Vec<Todo>)],
+    ) -> Result<(), CheckError> {
+        let mut by_tag: BTreeMap<&str, BTreeMap<&Path, Vec<&Todo>>> = BTreeMap::new();
+        for (file, todos) in todos {
+            for todo in todos {
+                let files = by_tag.entry(&todo.tag).or_default();
+                files.entry(file.as_path()).or_default().push(todo);
+            }
+        }
+        let mut inventory = String::new();
+        for (tag, files) in by_tag {
+            let count: usize = files.values().map(Vec::len).sum();
+            let _ = writeln!(inventory, "# {} ({})\n", tag, count);
+            for (file, todos) in files {
+                let _ = writeln!(inventory, "## {}\n", file.display());
+                for todo in todos {
+                    let _ = writeln!(inventory, "- {}: {}", todo.line, todo.text);
+                }
+                inventory.push('\n');
+            }
+        }
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(CheckError::WriteOutput)?;
+        }
+        std::fs::write(path, inventory).map_err(CheckError::WriteOutput)
+    }
+    pub(crate) fn execute(&self, repository: &Path, fix: bool) -> Result<(), CheckError> {
+        if fix {
+            return Err(CheckError::NoFix);
+        }
+        let finder = self.finder()?;
+        let reference = Regex::new(&self.reference)?;
+        let added = match &self.base {
+            Some(base) => Some(AddedLines::new(repository, base, self.scope)?),
+            None => None,
+        };
+        let files = scan::list(repository, self.scope, &self.include, &self.exclude)?;
+        let todos = scan::scan(repository, &files, |file, content| {
+            if !file.attributes.is_text(content) {
+                return vec![];
+            }
+            let markdown = file
+                .path
+                .extension()
+                .is_some_and(|e| e == "md" || e == "markdown");
+            let todos = finder.find(&String::from_utf8_lossy(content), markdown);
+            if todos.is_empty() {
+                return vec![];
+            }
+            vec![(file.path.clone(), todos)]
+        })?;
+        if let Some(inventory) = &self.inventory {
+            self.write_inventory(&repository.join(inventory), &todos)?;
+        }
+        let mut findings = vec![];
+        for (path, todos) in &todos {
+            for todo in todos {
+                let message = match &todo.reference {
+                    Some(r) if reference.is_match(r) => continue,
+                    Some(r) => format!(
+                        "{}({}) has an invalid reference, expected one matching `{}`",
+                        todo.tag, r, self.reference
+                    ),
+                    None => format!("{} without reference, e.g. {}(#123)", todo.tag, todo.tag),
+                };
+                if added.as_ref().is_some_and(|a| !a.contains(path, todo.line)) {
+                    continue;
+                }
+                findings.push(Finding {
+                    location: path.display().to_string(),
+                    line: Some(todo.line),
+                    column: Some(todo.column),
+                    message,
+                });
+            }
+        }
+        if !findings.is_empty() {
+            return Err(CheckError::Findings(findings));
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn find_todos() {
+    let check: Todos = serde_yaml::from_str("{}").unwrap();
+    let todos = check.finder().unwrap().find(
+        "// TODO(#12): a\nlet todo = 1; // FIXME fix\n# HACK(JIRA-4)\nXXXL TODOS",
+        false,
+    );
+    let tags: Vec<_> = todos
+        .iter()
+        .map(|t| (t.tag.as_str(), t.reference.as_deref(), t.line, t.column))
+        .collect();
+    assert_eq!(
+        tags,
+        [
+            ("TODO", Some("#12"), 1, 4),
+            ("FIXME", None, 2, 18),
+            ("HACK", Some("JIRA-4"), 3, 3)
+        ]
+    );
+    assert_eq!(todos[1].text, "FIXME fix");
+    let reference = Regex::new(&check.reference).unwrap();
+    assert!(reference.is_match("#12") && reference.is_match("JIRA-4"));
+    assert!(!reference.is_match("bob"));
+}
+
+#[test]
+fn ignore_strings_and_prose() {
+    let check: Todos = serde_yaml::from_str("{}").unwrap();
+    let finder = check.finder().unwrap();
+    let todos = finder.find(
+        r#"let status = "TODO";
+println!("XXX-XXX");
+See the TODO list.
+/* HACK(#1) */
+ * FIXME in a block comment
+-- TODO(#2)
+<!-- XXX -->
+"#,
+        false,
+    );
+    let tags: Vec<_> = todos.iter().map(|t| (t.tag.as_str(), t.line)).collect();
+    assert_eq!(tags, [("HACK", 4), ("FIXME", 5), ("TODO", 6), ("XXX", 7)]);
+    let todos = finder.find("# TODO\n\nSteps <!-- FIXME -->\n", true);
+    let tags: Vec<_> = todos.iter().map(|t| (t.tag.as_str(), t.line)).collect();
+    assert_eq!(tags, [("FIXME", 3)]);
+}
+
+#[test]
+fn several_tags_per_comment() {
+    let check: Todos = serde_yaml::from_str("{}").unwrap();
+    let todos = check
+        .finder()
+        .unwrap()
+        .find("let a = 1; // TODO(#1) and FIXME\n", false);
+    let tags: Vec<_> = todos
+        .iter()
+        .map(|t| (t.tag.as_str(), t.reference.as_deref(), t.column))
+        .collect();
+    assert_eq!(tags, [("TODO", Some("#1"), 15), ("FIXME", None, 28)]);
+    assert_eq!(todos[1].text, "FIXME");
+}
@@ -0,0 +1,220 @@
+//! Presentation of the check progress and results.
+use std::io::{IsTerminal, Write};
+use std::path::Path;
+use std::time::Duration;
+
+use colored::Colorize;
+
+use crate::errors::{CheckError, RunCommandError};
+use crate::runner::{Report, Status};
+
+/// How results are rendered, deduced from the terminal and the environment.
+#[derive(Clone, Copy, Debug)]
+pub struct Style {
+    /// Use ANSI colors.
+    pub color: bool,
+    /// Use emojis rather than ASCII status markers.
+    pub unicode: bool,
+    /// Show live spinners for the running check.
+    pub spinners: bool,
+}
+impl Style {
+    /// Detect the style from whether stdout is a terminal and the `NO_COLOR` and `CLICOLOR_FORCE`
+    /// environment variables. `plain` forces ASCII output without spinners.
+    pub fn detect(plain: bool) -> Self {
+        let terminal = std::io::stdout().is_terminal();
+        let env_set = |var: &str| std::env::var(var).is_ok_and(|v| !v.is_empty() && v != "0");
+        let color = if std::env::var("NO_COLOR").is_ok_and(|v| !v.is_empty()) {
+            false
+        } else {
+            env_set("CLICOLOR_FORCE") || terminal
+        };
+        Self {
+            color,
+            unicode: terminal && !plain,
+            spinners: terminal && !plain,
+        }
+    }
+    /// Apply the color setting globally.
+    pub fn apply(&self) {
+        colored::control::set_override(self.color);
+    }
+    fn marker(&self, status: Status) -> String {
+        match (status, self.unicode) {
+            (Status::Passed, true) => "✅".into(),
+            (Status::Failed, true) => "❌".into(),
+            (Status::Fixed, true) => "🟠".into(),
+            (Status::Skipped, true) => "⏩".into(),
+            (Status::Passed, false) => "[ok]".green().to_string(),
+            (Status::Failed, false) => "[FAIL]".red().to_string(),
+            (Status::Fixed, false) => "[fix]".yellow().to_string(),
+            (Status::Skipped, false) => "[skip]".into(),
+        }
+    }
+}
+
+/// Receives the events emitted while running checks.
+pub trait Reporter {
+    /// A run of `n_checks` checks starts.
+    fn start(&mut self, n_checks: usize, repository: &Path);
+    /// The check with 0-based `index` starts.
+    fn check_started(&mut self, index: usize, name: &str);
+    /// The check failed and its fix command is being run.
+    fn check_fixing(&mut self, name: &str);
+    /// The check (or its fix) succeeded.
+    fn check_passed(&mut self, name: &str, elapsed: Duration);
+    /// The check (or its fix) failed.
+    fn check_failed(&mut self, name: &str, error: &CheckError, elapsed: Duration);
+    /// The check with 0-based `index` is not executed.
+    fn check_skipped(&mut self, index: usize, name: &str, reason: &str);
+    /// The run is over.
+    fn finish(&mut self, report: &Report);
+    /// Informational message.
+    fn info(&mut self, message: &str);
+}
+
+/// Renders events on the standard output.
+pub struct Console {
+    style: Style,
+    n_checks: usize,
+    spinner: Option<indicatif::ProgressBar>,
+    /// Text of the line of the current check, preceding the status.
+    line: String,
+    fixing: bool,
+    /// Print the informational messages on the standard error.
+    info_stderr: bool,
+}
+impl Console {
+    pub fn new(style: Style) -> Self {
+        Self {
+            style,
+            n_checks: 0,
+            spinner: None,
+            line: String::new(),
+            fixing: false,
+            info_stderr: false,
+        }
+    }
+    /// Print the informational messages on the standard error, when the standard output is
+    /// data, e.g. a configuration.
+    pub fn info_on_stderr(mut self) -> Self {
+        self.info_stderr = true;
+        self
+    }
+    fn header(&self, index: usize, action: &str, name: &str) -> String {
+        format!(
+            "[{:>2}/{}] {:<9} {:<20}",
+            index + 1,
+            self.n_checks,
+            action,
+            name
+        )
+    }
+    fn flush() {
+        let _ = std::io::stdout().flush();
+    }
+    /// Clear the spinner, and complete the line of the current check with a status.
+    fn end_line(&mut self, status: Status, elapsed: Duration) {
+        let time = format!("{:.2} s", elapsed.as_secs_f32());
+        let end = if self.fixing && status == Status::Passed {
+            time
+        } else {
+            format!("{} {}", self.style.marker(status), time)
+        };
+        if let Some(spinner) = self.spinner.take() {
+            spinner.finish_and_clear();
+            println!("{}{}", self.line, end);
+        } else {
+            println!("{}", end);
+        }
+    }
+}
+impl Reporter for Console {
+    fn start(&mut self, n_checks: usize, repository: &Path) {
+        self.n_checks = n_checks;
+        println!("Executing {} checks in {:?}", n_checks, repository);
+    }
+    fn check_started(&mut self, index: usize, name: &str) {
+        self.fixing = false;
+        self.line = format!("{} ", self.header(index, "Executing", name));
+        if self.style.spinners {
+            let spinner = indicatif::ProgressBar::with_draw_target(
+                None,
+                indicatif::ProgressDrawTarget::stdout(),
+            )
+            .with_style(
+                indicatif::ProgressStyle::with_template("{prefix}{spinner} {elapsed}")
+                    .unwrap()
+                    .tick_chars("⠋⠙⠹⠸⠼⠴⠦⠧⠇⠏ "),
+            )
+            .with_prefix(self.line.clone());
+            spinner.enable_steady_tick(Duration::from_millis(100));
+            self.spinner = Some(spinner);
+        } else {
+            print!("{}", self.line);
+            Self::flush();
+        }
+    }
+    fn check_fixing(&mut self, _name: &str) {
+        self.fixing = true;
+        let marker = format!("{} ", self.style.marker(Status::Fixed));
+        self.line.push_str(&marker);
+        if let Some(spinner) = &self.spinner {
+            spinner.set_prefix(self.line.clone());
+        } else {
+            print!("{}", marker);
+            Self::flush();
+        }
+    }
+    fn check_passed(&mut self, _name: &str, elapsed: Duration) {
+        self.end_line(Status::Passed, elapsed);
+    }
+    fn check_failed(&mut self, _name: &str, error: &CheckError, elapsed: Duration) {
+        self.end_line(Status::Failed, elapsed);
+        println!("\n{}", error.to_string().red());
+        if let CheckError::RunCommand(RunCommandError::StatusCode { output, .. }) = error {
+            println!("{}", output);
+        }
+    }
+    fn check_skipped(&mut self, index: usize, name: &str, reason: &str) {
+        println!(
+            "{} {}",
+            self.header(index, "Skipping", name),
+            format!("{} {}", self.style.marker(Status::Skipped), reason).dimmed()
+        );
+    }
+    fn finish(&mut self, report: &Report) {
+        let mut details = String::new();
+        for (status, label) in [(Status::Fixed, "fixed"), (Status::Skipped, "skipped")] {
+            match report.count(status) {
+                0 => {}
+                n => details.push_str(&format!(", {} {}", n, label)),
+            }
+        }
+        let elapsed = report.duration.as_secs_f32();
+        match report.count(Status::Failed) {
+            0 => println!(
+                "{} All {} checks passed in {:.2} s{}",
+                self.style.marker(Status::Passed),
+                report.checks.len() - report.count(Status::Skipped),
+                elapsed,
+                details
+            ),
+            failed => println!(
+                "{} {} of {} checks failed in {:.2} s{}",
+                self.style.marker(Status::Failed),
+                failed,
+                self.n_checks,
+                elapsed,
+                details
+            ),
+        }
+    }
+    fn info(&mut self, message: &str) {
+        if self.info_stderr {
+            eprintln!("{}", message);
+        } else {
+            println!("{}", message);
+        }
+    }
+}
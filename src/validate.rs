@@ -0,0 +1,450 @@
+//! Diagnostics on the configuration file, pointing to the offending lines.
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use crate::scan::LineIndex;
+use crate::{Check, CheckEntry, Config};
+
+/// Position in a source file, 1-based.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+    /// Number of characters to highlight.
+    pub length: usize,
+}
+
+#[derive(Debug)]
+pub struct Diagnostic {
+    pub message: String,
+    pub location: Option<Location>,
+    pub help: Option<String>,
+}
+
+/// Errors found in a configuration file, rendered with source snippets.
+#[derive(thiserror::Error, Debug)]
+#[error("{}", self.render())]
+pub struct Diagnostics {
+    path: PathBuf,
+    text: String,
+    pub diagnostics: Vec<Diagnostic>,
+}
+impl Diagnostics {
+    fn render(&self) -> String {
+        let lines: Vec<_> = self.text.lines().collect();
+        let mut out = String::new();
+        for (i, diagnostic) in self.diagnostics.iter().enumerate() {
+            if i > 0 {
+                out.push('\n');
+            }
+            out.push_str(&format!("error: {}\n", diagnostic.message));
+            let number = diagnostic
+                .location
+                .map(|l| l.line.to_string())
+                .unwrap_or_default();
+            let pad = " ".repeat(number.len());
+            if let Some(location) = &diagnostic.location {
+                out.push_str(&format!(
+                    "{} --> {}:{}:{}\n",
+                    pad,
+                    self.path.display(),
+                    location.line,
+                    location.column
+                ));
+                if let Some(line) = lines.get(location.line - 1) {
+                    out.push_str(&format!("{} |\n", pad));
+                    out.push_str(&format!("{} | {}\n", number, line));
+                    out.push_str(&format!(
+                        "{} | {}{}\n",
+                        pad,
+                        " ".repeat(location.column - 1),
+                        "^".repeat(location.length.max(1))
+                    ));
+                }
+            }
+            if let Some(help) = &diagnostic.help {
+                out.push_str(&format!("{} = help: {}\n", pad, help));
+            }
+        }
+        out.trim_end().into()
+    }
+}
+
+/// Byte ranges of an item of `checks` and of its keys.
+pub(crate) type CheckSpans = (Range<usize>, Vec<(String, Range<usize>)>);
+
+/// Configuration source, used to locate diagnostics.
+pub struct Source<'a> {
+    path: &'a Path,
+    text: &'a str,
+    /// Spans of the checks, for sources that are not in YAML, e.g. the TOML of a `Cargo.toml`.
+    checks: Option<Vec<CheckSpans>>,
+}
+impl<'a> Source<'a> {
+    pub fn new(path: &'a Path, text: &'a str) -> Self {
+        Self {
+            path,
+            text,
+            checks: None,
+        }
+    }
+    /// Locate the checks with these spans rather than by scanning YAML.
+    pub(crate) fn with_checks(mut self, checks: Vec<CheckSpans>) -> Self {
+        self.checks = Some(checks);
+        self
+    }
+    fn location(&self, span: &Range<usize>) -> Location {
+        let (line, column) = LineIndex::new(self.text).position(span.start);
+        let text = &self.text[span.clone()];
+        Location {
+            line,
+            column,
+            length: text.lines().next().unwrap_or_default().chars().count(),
+        }
+    }
+    pub fn error(&self, diagnostics: Vec<Diagnostic>) -> Diagnostics {
+        Diagnostics {
+            path: self.path.into(),
+            text: self.text.into(),
+            diagnostics,
+        }
+    }
+    /// Convert a deserialization error, suggesting the closest name for unknown fields or
+    /// variants, and pointing to the unknown field rather than to the start of the item.
+    pub fn yaml_error(&self, error: &serde_yaml::Error) -> Diagnostic {
+        let mut message = error.to_string();
+        if let Some(i) = message.rfind(" at line ") {
+            message.truncate(i);
+        }
+        let mut location = error.location().map(|l| Location {
+            line: l.line(),
+            column: l.column(),
+            length: 1,
+        });
+        let mut help = None;
+        if let Some((kind, unknown, suggestion)) = unknown_name(&message) {
+            help = suggestion;
+            location = location.map(|l| {
+                if kind == "unknown field " {
+                    self.find_key(l, unknown).unwrap_or(l)
+                } else {
+                    Location {
+                        length: unknown.len(),
+                        ..l
+                    }
+                }
+            });
+        }
+        Diagnostic {
+            message,
+            location,
+            help,
+        }
+    }
+    /// Convert a TOML deserialization error, suggesting the closest name for unknown fields or
+    /// variants.
+    pub fn toml_error(&self, error: &toml::de::Error) -> Diagnostic {
+        let message = error.message().trim_end().to_string();
+        Diagnostic {
+            location: error.span().map(|s| self.location(&s)),
+            help: unknown_name(&message).and_then(|(_, _, help)| help),
+            message,
+        }
+    }
+    fn lines(&self) -> impl Iterator<Item = (usize, &'a str)> {
+        self.text.lines().enumerate().map(|(i, l)| (i + 1, l))
+    }
+    /// Find a key in the mapping starting at `start`.
+    fn find_key(&self, start: Location, key: &str) -> Option<Location> {
+        let indent = start.column - 1;
+        self.lines()
+            .skip(start.line - 1)
+            .enumerate()
+            // Stop at the end of the mapping
+            .take_while(|(i, (_, line))| {
+                *i == 0 || line.trim().is_empty() || indentation(line) >= indent
+            })
+            .find_map(|(_, (number, line))| {
+                let content = line.get(indent..)?.trim_start_matches(['-', ' ']);
+                let column = line.len() - content.len() + 1;
+                let content = content.trim_start_matches(['"', '\'']);
+                let rest = content.strip_prefix(key)?;
+                rest.trim_start_matches(['"', '\''])
+                    .starts_with(':')
+                    .then_some(Location {
+                        line: number,
+                        column,
+                        length: line.len() + 1 - column - rest.len(),
+                    })
+            })
+    }
+    /// Find the item with the given index in the top-level `checks` sequence.
+    /// This assumes that the YAML uses the block style.
+    pub fn find_check(&self, index: usize) -> Option<Location> {
+        if let Some(checks) = &self.checks {
+            let (span, _) = checks.get(index)?;
+            return Some(Location {
+                length: 1,
+                ..self.location(span)
+            });
+        }
+        let mut lines = self.lines().skip_while(|(_, l)| !l.starts_with("checks:"));
+        lines.next()?;
+        let mut indent = None;
+        lines
+            .filter_map(|(number, line)| {
+                let column = indentation(line);
+                let item = line[column..].starts_with("- ");
+                (item && *indent.get_or_insert(column) == column).then_some(Location {
+                    line: number,
+                    column: column + 3,
+                    length: 1,
+                })
+            })
+            .nth(index)
+    }
+    /// Location of a key of the item with the given index in the `checks` sequence.
+    pub fn find_check_key(&self, index: usize, key: &str) -> Option<Location> {
+        let item = self.find_check(index)?;
+        if let Some(checks) = &self.checks {
+            let (_, keys) = &checks[index];
+            let span = keys.iter().find_map(|(k, span)| (k == key).then_some(span));
+            return Some(span.map_or(item, |span| self.location(span)));
+        }
+        self.find_key(item, key).or(Some(item))
+    }
+}
+
+fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+/// Strings quoted with backquotes in a serde error message.
+fn backquoted(message: &str) -> Vec<&str> {
+    message.split('`').skip(1).step_by(2).collect()
+}
+
+/// Kind, name and suggested replacement of an unknown field or variant in a serde error message.
+fn unknown_name(message: &str) -> Option<(&'static str, &str, Option<String>)> {
+    ["unknown field ", "unknown variant "]
+        .into_iter()
+        .find_map(|kind| {
+            let start = message.find(kind)?;
+            let quoted = backquoted(&message[start + kind.len()..]);
+            let (unknown, expected) = quoted.split_first()?;
+            Some((kind, *unknown, suggest(unknown, expected.iter().copied())))
+        })
+}
+
+/// Suggest the closest candidate to a mistyped name.
+pub fn suggest<'a>(name: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<String> {
+    candidates
+        .into_iter()
+        .map(|c| (strsim::jaro_winkler(name, c), c))
+        .filter(|(score, _)| *score > 0.8)
+        .max_by(|(a, _), (b, _)| a.total_cmp(b))
+        .map(|(_, c)| format!("did you mean `{}`?", c))
+}
+
+impl Config {
+    /// Semantic validation of a deserialized configuration.
+    pub fn validate(&self, source: &Source) -> Vec<Diagnostic> {
+        let mut diagnostics = vec![];
+        let mut names = HashMap::new();
+        for (i, check) in self.checks.iter().enumerate() {
+            // A preset can be used several times, e.g. in different folders. The names of its
+            // checks are compared once expanded, with `unique_names`.
+            if matches!(check.check, Check::Preset(_)) {
+            } else if let Some(first) = names.insert(check.name(), i) {
+                diagnostics.push(Diagnostic {
+                    message: format!("checks[{}]: duplicate check name `{}`", i, check.name()),
+                    location: source.find_check_key(i, "name"),
+                    help: Some(format!(
+                        "the name is already used by checks[{}]; names must be unique to select checks",
+                        first
+                    )),
+                });
+                names.insert(check.name(), first);
+            }
+            if let Check::Command { name, matrix, .. } = &check.check {
+                for (axis, values) in matrix {
+                    if !name.contains(&format!("{{{}}}", axis)) {
+                        diagnostics.push(Diagnostic {
+                            message: format!(
+                                "checks[{}]: the name must contain the `{{{}}}` placeholder of the matrix axis",
+                                i, axis
+                            ),
+                            location: source.find_check_key(i, "name"),
+                            help: Some("expanded checks must have distinct names".into()),
+                        });
+                    }
+                    if values.is_empty() {
+                        diagnostics.push(Diagnostic {
+                            message: format!("checks[{}]: the matrix axis `{}` is empty", i, axis),
+                            location: source.find_check_key(i, "matrix"),
+                            help: None,
+                        });
+                    }
+                }
+            }
+            if let Check::Command {
+                command,
+                uses,
+                with,
+                ..
+            } = &check.check
+            {
+                if command.is_none() && uses.is_none() {
+                    diagnostics.push(Diagnostic {
+                        message: format!("checks[{}]: missing field `command`", i),
+                        location: source.find_check(i),
+                        help: Some("set a `command`, or use a template with `uses`".into()),
+                    });
+                }
+                if !with.is_empty() && uses.is_none() {
+                    diagnostics.push(Diagnostic {
+                        message: format!("checks[{}]: `with` requires a template in `uses`", i),
+                        location: source.find_check_key(i, "with"),
+                        help: None,
+                    });
+                }
+            }
+            if let Check::Command {
+                version: Some(_),
+                version_command: None,
+                uses: None,
+                ..
+            } = &check.check
+            {
+                diagnostics.push(Diagnostic {
+                    message: format!(
+                        "checks[{}]: a `version_command` is required to check against `version`",
+                        i
+                    ),
+                    location: source.find_check_key(i, "version"),
+                    help: None,
+                });
+            }
+            if let Check::LicenseHeader(license) = &check.check {
+                for field in license.missing_fields() {
+                    diagnostics.push(Diagnostic {
+                        message: format!(
+                            "checks[{}]: the header uses `{{{}}}`, but `{}` is not set",
+                            i, field, field
+                        ),
+                        location: source.find_check(i),
+                        help: Some(format!("set `{}`, or change the `header`", field)),
+                    });
+                }
+            }
+        }
+        diagnostics
+    }
+}
+
+/// Check that the names are still unique once presets and matrices are expanded, which
+/// [`Config::validate`] cannot see.
+pub(crate) fn unique_names(checks: &[CheckEntry]) -> anyhow::Result<()> {
+    let mut names = HashMap::new();
+    for check in checks {
+        if let Some(first) = names.insert(check.name(), check) {
+            let origin = if first.origin == check.origin {
+                check.origin.clone()
+            } else {
+                format!("{} and {}", first.origin, check.origin)
+            };
+            anyhow::bail!(
+                "Duplicate check name `{}` after expanding presets and matrices, in {}. Rename one of the checks, or leave out the one of a preset with `remove`.",
+                check.name(),
+                origin
+            );
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn locate_errors() {
+    let text = "checks:
+  - type: version
+    version: \">=0.1\"
+  - type: command
+    name: fmt
+    comand: cargo fmt
+";
+    let source = Source::new(Path::new("checkalot.yaml"), text);
+    let error = serde_yaml::from_str::<Config>(text).unwrap_err();
+    let diagnostic = source.yaml_error(&error);
+    assert_eq!(
+        diagnostic.location,
+        Some(Location {
+            line: 6,
+            column: 5,
+            length: 6
+        })
+    );
+    assert_eq!(diagnostic.help.unwrap(), "did you mean `command`?");
+    assert_eq!(source.find_check(1).unwrap().line, 4);
+}
+
+#[test]
+fn duplicate_expanded_names() {
+    let config = Config::from_source(
+        Path::new("checkalot.yaml"),
+        "checks:
+  - type: command
+    name: test-{toolchain}
+    command: cargo +{toolchain} test
+    matrix:
+      toolchain: [stable, nightly]
+  - type: command
+    name: test-nightly
+    command: cargo +nightly test --all-features",
+    )
+    .unwrap();
+    let checks = crate::matrix::expand(config.checks);
+    let error = unique_names(&checks).unwrap_err();
+    assert!(error.to_string().contains("`test-nightly`"));
+}
+
+#[test]
+fn locate_manifest_errors() {
+    let folder = tempfile::tempdir().unwrap();
+    let manifest = folder.path().join("Cargo.toml");
+    let parse = |text: &str| {
+        std::fs::write(&manifest, text).unwrap();
+        let error = Config::parse_root(&folder.path().join("checkalot.yaml")).unwrap_err();
+        let diagnostics = error.downcast::<Diagnostics>().unwrap();
+        let diagnostic = diagnostics.diagnostics.into_iter().next().unwrap();
+        (diagnostic.location.unwrap(), diagnostic.help)
+    };
+    let (location, _) = parse(
+        "[package]
+name = \"crate\"
+
+[[package.metadata.checkalot.checks]]
+type = \"git-is-clean\"
+
+[[package.metadata.checkalot.checks]]
+type = \"command\"
+command = \"cargo fmt --check\"
+name = \"git-is-clean\"
+",
+    );
+    assert_eq!(
+        location,
+        Location {
+            line: 10,
+            column: 1,
+            length: 4
+        }
+    );
+    let (location, help) = parse(
+        "[package.metadata.checkalot]
+checks = [{ type = \"command\", name = \"fmt\", comand = \"cargo fmt\" }]
+",
+    );
+    assert_eq!(location.line, 2);
+    assert_eq!(help.unwrap(), "did you mean `command`?");
+}
@@ -0,0 +1,318 @@
+//! Interpolation of `{variable}` and `${ENV}` placeholders in commands, folders and output paths.
+use std::cell::OnceCell;
+use std::path::{Path, PathBuf};
+
+use crate::validate::suggest;
+use crate::{Check, CheckEntry};
+
+const NAMES: [&str; 7] = [
+    "repo",
+    "folder",
+    "check",
+    "git.branch",
+    "git.sha",
+    "cache_dir",
+    "bundle_dir",
+];
+
+/// Values of the variables for a check. The git information is only queried when used.
+pub struct Variables {
+    repo: PathBuf,
+    check: String,
+    bundle_dir: Option<PathBuf>,
+    git_branch: OnceCell<Result<String, String>>,
+    git_sha: OnceCell<Result<String, String>>,
+}
+
+/// Quoting context of a placeholder in a command.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Quotes {
+    None,
+    Single,
+    Double,
+}
+
+impl Variables {
+    pub fn new(repo: &Path, check: &str, bundle_dir: Option<&Path>) -> Self {
+        Self {
+            repo: repo.into(),
+            check: check.into(),
+            bundle_dir: bundle_dir.map(Into::into),
+            git_branch: OnceCell::new(),
+            git_sha: OnceCell::new(),
+        }
+    }
+    fn git(
+        &self,
+        cell: &OnceCell<Result<String, String>>,
+        args: &[&str],
+    ) -> Result<String, String> {
+        cell.get_or_init(|| {
+            crate::git::git(&self.repo, args)
+                .map(|s| s.trim().to_string())
+                .map_err(|e| format!("Failed to get git information: {}", e))
+        })
+        .clone()
+    }
+    /// Value of a variable. `folder` is the execution folder, which is not known yet while
+    /// interpolating the `folder` field itself.
+    fn get(&self, name: &str, folder: Option<&Path>) -> Result<String, String> {
+        let path = |p: &Path| p.to_string_lossy().into_owned();
+        match name {
+            "repo" => Ok(path(&self.repo)),
+            "folder" => folder.map(path).ok_or_else(folder_in_folder),
+            "check" => Ok(self.check.clone()),
+            "git.branch" => self.git(&self.git_branch, &["rev-parse", "--abbrev-ref", "HEAD"]),
+            "git.sha" => self.git(&self.git_sha, &["rev-parse", "HEAD"]),
+            "cache_dir" => crate::cache_dir()
+                .map(|p| path(&p))
+                .map_err(|e| e.to_string()),
+            "bundle_dir" => self
+                .bundle_dir
+                .as_deref()
+                .map(path)
+                .ok_or_else(|| "`{bundle_dir}` requires a `bundle` in the configuration".into()),
+            _ => Err(unknown(name)),
+        }
+    }
+    /// Replace the placeholders in a command executed in `folder`, escaping the values so that
+    /// they are kept as is when splitting the command into arguments.
+    pub fn command(&self, command: &str, folder: &Path) -> Result<String, String> {
+        self.interpolate(command, Some(folder), true)
+    }
+    /// Replace the placeholders in a path, for a command executed in `folder`. This is `None` for
+    /// the `folder` field, where `{folder}` cannot be used.
+    pub fn path(&self, path: &Path, folder: Option<&Path>) -> Result<PathBuf, String> {
+        Ok(self
+            .interpolate(&path.to_string_lossy(), folder, false)?
+            .into())
+    }
+    fn interpolate(
+        &self,
+        text: &str,
+        folder: Option<&Path>,
+        shell: bool,
+    ) -> Result<String, String> {
+        replace(text, shell, |rest| {
+            Ok(if rest.starts_with("{{") {
+                Some((2, "{".into(), false))
+            } else if let Some(name) = rest.strip_prefix('{').and_then(variable_name) {
+                Some((name.len() + 2, self.get(name, folder)?, true))
+            } else if let Some(name) = rest.strip_prefix("${").and_then(variable_name) {
+                let value = std::env::var(name)
+                    .map_err(|_| format!("The environment variable `{}` is not set", name))?;
+                Some((name.len() + 3, value, true))
+            } else {
+                None
+            })
+        })
+    }
+}
+
+/// Replacement of the start of a text: length replaced, value, and whether to quote the value.
+type Replacement = Option<(usize, String, bool)>;
+
+/// Replace the placeholders found by `placeholder` at each position of a text. In commands
+/// (`shell`), the values are escaped according to the quotes around them.
+fn replace<E>(
+    text: &str,
+    shell: bool,
+    mut placeholder: impl FnMut(&str) -> Result<Replacement, E>,
+) -> Result<String, E> {
+    let mut out = String::new();
+    let mut quotes = Quotes::None;
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        if let Some((len, value, quoted)) = placeholder(&text[i..])? {
+            while chars.next_if(|(j, _)| *j < i + len).is_some() {}
+            out.push_str(&if shell && quoted {
+                quote(&value, quotes)
+            } else {
+                value
+            });
+            continue;
+        }
+        if shell {
+            match (c, quotes) {
+                ('\'', Quotes::None) => quotes = Quotes::Single,
+                ('\'', Quotes::Single) => quotes = Quotes::None,
+                ('"', Quotes::None) => quotes = Quotes::Double,
+                ('"', Quotes::Double) => quotes = Quotes::None,
+                ('\\', Quotes::None | Quotes::Double) => {
+                    out.push(c);
+                    if let Some((_, next)) = chars.next() {
+                        out.push(next);
+                    }
+                    continue;
+                }
+                _ => {}
+            }
+        }
+        out.push(c);
+    }
+    Ok(out)
+}
+
+/// Replace the `{name}` placeholders of the given values in a command, escaping the values like
+/// the variables. Other placeholders, and `{{`, are kept for the interpolation of the variables.
+pub(crate) fn substitute_command(command: &str, values: &[(&str, &str)]) -> String {
+    replace(command, true, |rest| {
+        Ok::<_, std::convert::Infallible>(if rest.starts_with("{{") {
+            Some((2, "{{".into(), false))
+        } else {
+            rest.strip_prefix('{')
+                .and_then(variable_name)
+                .and_then(|name| values.iter().find(|(n, _)| *n == name))
+                .map(|(name, value)| (name.len() + 2, value.to_string(), true))
+        })
+    })
+    .unwrap_or_else(|e| match e {})
+}
+
+fn unknown(name: &str) -> String {
+    let help = suggest(name, NAMES)
+        .map(|s| format!(" ({})", s))
+        .unwrap_or_default();
+    format!(
+        "Unknown variable `{{{}}}`{}. Use `{{{{` for a literal brace.",
+        name, help
+    )
+}
+
+fn folder_in_folder() -> String {
+    "`{folder}` is the execution folder, and cannot be used in `folder`".into()
+}
+
+/// Check the `{variable}` placeholders of a text, which is a `folder` field if `in_folder` is set.
+fn check_placeholders(text: &str, in_folder: bool) -> Result<(), String> {
+    let mut rest = text;
+    while let Some(i) = rest.find('{') {
+        rest = &rest[i..];
+        if let Some(escaped) = rest.strip_prefix("{{") {
+            rest = escaped;
+            continue;
+        }
+        rest = &rest[1..];
+        // `${ENV}` variables are only known when running.
+        if text[..text.len() - rest.len() - 1].ends_with('$') {
+            continue;
+        }
+        match variable_name(rest) {
+            Some("folder") if in_folder => return Err(folder_in_folder()),
+            Some(name) if !NAMES.contains(&name) => return Err(unknown(name)),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Check that the command checks only use known variables, once templates and matrices are
+/// expanded, so that mistakes are reported when loading the configuration.
+pub(crate) fn validate(checks: &[CheckEntry]) -> anyhow::Result<()> {
+    for check in checks {
+        let Check::Command {
+            command,
+            fix_command,
+            version_command,
+            folder,
+            output,
+            ..
+        } = &check.check
+        else {
+            continue;
+        };
+        let mut fields = vec![];
+        for (field, spec) in [
+            ("command", command),
+            ("fix_command", fix_command),
+            ("version_command", version_command),
+        ] {
+            fields.extend(spec.as_ref().map(|s| (field, s.command().clone())));
+        }
+        for (field, path) in [("folder", folder), ("output", output)] {
+            fields.extend(
+                path.as_ref()
+                    .map(|p| (field, p.to_string_lossy().into_owned())),
+            );
+        }
+        for (field, text) in fields {
+            check_placeholders(&text, field == "folder").map_err(|e| {
+                anyhow::anyhow!("Check `{}`, field `{}`: {}", check.name(), field, e)
+            })?;
+        }
+    }
+    Ok(())
+}
+
+/// Name of a placeholder at the start of `s`, if followed by a closing brace.
+fn variable_name(s: &str) -> Option<&str> {
+    let end = s.find('}')?;
+    let name = &s[..end];
+    let mut chars = name.chars();
+    let valid = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.');
+    valid.then_some(name)
+}
+
+fn quote(value: &str, quotes: Quotes) -> String {
+    match quotes {
+        Quotes::None => shell_words::quote(value).into_owned(),
+        Quotes::Single => value.replace('\'', r"'\''"),
+        Quotes::Double => value
+            .chars()
+            .flat_map(|c| {
+                let escape = matches!(c, '\\' | '"' | '$' | '`').then_some('\\');
+                escape.into_iter().chain([c])
+            })
+            .collect(),
+    }
+}
+
+#[test]
+fn interpolate() {
+    let variables = Variables::new(Path::new("/my repo"), "it's", None);
+    let split = |command: &str| {
+        shell_words::split(&variables.command(command, Path::new("sub")).unwrap()).unwrap()
+    };
+    assert_eq!(
+        split(r#"echo {repo}/x "{check} \"q\"" '{check}' {folder} awk '{print $1}' {{repo}"#),
+        [
+            "echo",
+            "/my repo/x",
+            r#"it's "q""#,
+            "it's",
+            "sub",
+            "awk",
+            "{print $1}",
+            "{repo}"
+        ]
+    );
+    assert_eq!(
+        variables
+            .path(Path::new("target/{check}.txt"), None)
+            .unwrap(),
+        Path::new("target/it's.txt")
+    );
+    assert!(variables
+        .command("echo {chek}", Path::new(""))
+        .unwrap_err()
+        .contains("did you mean `check`?"));
+    assert!(variables
+        .command("echo {bundle_dir}", Path::new(""))
+        .is_err());
+    assert!(variables
+        .command("echo ${CHECKALOT_UNSET_VARIABLE}", Path::new(""))
+        .is_err());
+}
+
+#[test]
+fn validate_placeholders() {
+    assert!(check_placeholders("echo {repo} ${HOME} {{chek}} awk '{print $1}'", false).is_ok());
+    assert!(check_placeholders("echo {chek}", false)
+        .unwrap_err()
+        .contains("did you mean `check`?"));
+    assert!(check_placeholders("{folder}/sub", true).is_err());
+    assert!(check_placeholders("target/{folder}.txt", false).is_ok());
+}
@@ -0,0 +1,208 @@
+//! Built-in `whitespace` check, for trailing whitespace, final newlines, line endings, tabs in
+//! YAML and byte order marks.
+use std::path::Path;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::errors::CheckError;
+use crate::scan::{self, Attributes, Scope};
+
+const BOM: &[u8] = b"\xef\xbb\xbf";
+
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Whitespace {
+    /// Files to scan.
+    #[serde(default, skip_serializing_if = "Scope::is_default")]
+    scope: Scope,
+    /// Patterns (in the `.gitignore` syntax) of the files to scan. All files if empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    include: Vec<String>,
+    /// Patterns (in the `.gitignore` syntax) of the files to ignore.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    exclude: Vec<String>,
+    /// Report spaces and tabs at the end of lines.
+    #[serde(
+        default = "crate::config::default_true",
+        skip_serializing_if = "crate::config::is_true"
+    )]
+    trailing_whitespace: bool,
+    /// Report files not ending with a newline.
+    #[serde(
+        default = "crate::config::default_true",
+        skip_serializing_if = "crate::config::is_true"
+    )]
+    final_newline: bool,
+    /// Report CRLF line endings, once per file on the first one, unless the file has the
+    /// `eol=crlf` attribute.
+    #[serde(
+        default = "crate::config::default_true",
+        skip_serializing_if = "crate::config::is_true"
+    )]
+    crlf: bool,
+    /// Report tabs in the indentation of YAML files, where they are not allowed.
+    #[serde(
+        default = "crate::config::default_true",
+        skip_serializing_if = "crate::config::is_true"
+    )]
+    yaml_tabs: bool,
+    /// Report UTF-8 byte order marks.
+    #[serde(
+        default = "crate::config::default_true",
+        skip_serializing_if = "crate::config::is_true"
+    )]
+    bom: bool,
+}
+
+/// Problem in a file, at a line.
+struct Problem {
+    line: usize,
+    message: &'static str,
+    fixable: bool,
+}
+
+impl Whitespace {
+    fn scan(&self, path: &Path, attributes: Attributes, content: &[u8]) -> Vec<Problem> {
+        let mut problems = vec![];
+        let mut problem = |line, message, fixable| {
+            problems.push(Problem {
+                line,
+                message,
+                fixable,
+            })
+        };
+        // Files with `-text`, `-diff` or `binary` attributes, or detected as binary, are skipped.
+        if content.is_empty() || !attributes.is_text(content) {
+            return problems;
+        }
+        if self.bom && content.starts_with(BOM) {
+            problem(1, "byte order mark", true);
+        }
+        let yaml = path.extension().is_some_and(|e| e == "yaml" || e == "yml");
+        let mut crlf = false;
+        let lines = content.strip_suffix(b"\n").unwrap_or(content);
+        for (i, line) in lines.split(|b| *b == b'\n').enumerate() {
+            let line = match line.strip_suffix(b"\r") {
+                Some(line) => {
+                    if self.crlf && !crlf && !attributes.crlf {
+                        problem(
+                            i + 1,
+                            "CRLF line endings in the file (first on this line)",
+                            true,
+                        );
+                        crlf = true;
+                    }
+                    line
+                }
+                None => line,
+            };
+            if self.trailing_whitespace && (line.ends_with(b" ") || line.ends_with(b"\t")) {
+                problem(i + 1, "trailing whitespace", true);
+            }
+            if self.yaml_tabs && yaml {
+                let indentation = line.iter().take_while(|b| **b == b' ' || **b == b'\t');
+                if indentation.clone().any(|b| *b == b'\t') {
+                    problem(i + 1, "tab in the indentation of YAML", false);
+                }
+            }
+        }
+        if self.final_newline && !content.ends_with(b"\n") {
+            problem(
+                lines.split(|b| *b == b'\n').count(),
+                "no newline at the end of the file",
+                true,
+            );
+        }
+        problems
+    }
+    /// Remove the fixable problems. Files with the `eol=crlf` attribute get CRLF line endings.
+    fn fix(&self, crlf_attribute: bool, content: &[u8]) -> Vec<u8> {
+        let mut content = content;
+        if self.bom {
+            content = content.strip_prefix(BOM).unwrap_or(content);
+        }
+        let lines = content.strip_suffix(b"\n").unwrap_or(content);
+        let mut out = Vec::with_capacity(content.len());
+        let mut eol: &[u8] = b"\n";
+        for line in lines.split(|b| *b == b'\n') {
+            let (mut line, cr) = match line.strip_suffix(b"\r") {
+                Some(line) => (line, true),
+                None => (line, false),
+            };
+            if self.trailing_whitespace {
+                while let Some(stripped) =
+                    line.strip_suffix(b" ").or_else(|| line.strip_suffix(b"\t"))
+                {
+                    line = stripped;
+                }
+            }
+            out.extend_from_slice(line);
+            eol = if crlf_attribute || (cr && !self.crlf) {
+                b"\r\n"
+            } else {
+                b"\n"
+            };
+            out.extend_from_slice(eol);
+        }
+        if !self.final_newline && !content.ends_with(b"\n") {
+            out.truncate(out.len() - eol.len());
+        }
+        out
+    }
+    pub(crate) fn execute(&self, repository: &Path, fix: bool) -> Result<(), CheckError> {
+        let files = scan::list(repository, self.scope, &self.include, &self.exclude)?;
+        let findings = scan::scan(repository, &files, |file, content| {
+            let problems = self.scan(&file.path, file.attributes, content);
+            if fix && problems.iter().any(|p| p.fixable) {
+                // The fix applies to the working tree, even when scanning staged files.
+                let path = repository.join(&file.path);
+                let result = std::fs::read(&path).and_then(|content| {
+                    std::fs::write(&path, self.fix(file.attributes.crlf, &content))
+                });
+                if let Err(e) = result {
+                    return vec![file.finding(None, format!("failed to fix: {}", e))];
+                }
+            }
+            problems
+                .into_iter()
+                .filter(|p| !fix || !p.fixable)
+                .map(|p| file.finding(Some(p.line), p.message))
+                .collect()
+        })?;
+        if !findings.is_empty() {
+            return Err(CheckError::Findings(findings));
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn whitespace() {
+    let check: Whitespace = serde_yaml::from_str("{}").unwrap();
+    let attributes = Attributes {
+        text: scan::Text::Auto,
+        crlf: false,
+    };
+    let content = b"\xef\xbb\xbfa: 1 \r\nb:\r\n\t- c\r\nd: 2";
+    let problems: Vec<_> = check
+        .scan(Path::new("a.yaml"), attributes, content)
+        .into_iter()
+        .map(|p| (p.line, p.message))
+        .collect();
+    assert_eq!(
+        problems,
+        [
+            (1, "byte order mark"),
+            (1, "CRLF line endings in the file (first on this line)"),
+            (1, "trailing whitespace"),
+            (3, "tab in the indentation of YAML"),
+            (4, "no newline at the end of the file")
+        ]
+    );
+    assert_eq!(check.fix(false, content), b"a: 1\nb:\n\t- c\nd: 2\n");
+    assert_eq!(check.fix(true, content), b"a: 1\r\nb:\r\n\t- c\r\nd: 2\r\n");
+    assert!(check
+        .scan(Path::new("a.bin"), attributes, b"\0 \n")
+        .is_empty());
+}